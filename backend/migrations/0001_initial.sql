@@ -0,0 +1,90 @@
+-- Table Structure
+-- Primary Key
+-- Creation Time
+-- Creator User Id (if applicable)
+-- Everything else
+
+create table submission(
+  submission_id bigserial primary key,
+  creation_time bigint not null default extract(epoch from now()) * 1000,
+  creator_user_id bigint not null,
+  code text not null
+);
+
+create table tournament(
+  tournament_id bigserial primary key,
+  creation_time bigint not null default extract(epoch from now()) * 1000,
+  creator_user_id bigint not null
+);
+
+-- invariant: tournament_id is valid
+create table tournament_data(
+  tournament_data_id bigserial primary key,
+  creation_time bigint not null default extract(epoch from now()) * 1000,
+  creator_user_id bigint not null,
+  tournament_id bigint not null references tournament(tournament_id),
+  -- tournament title
+  title text not null,
+  -- tournament description
+  description text  not null,
+  -- how many rounds in a matchup
+  n_rounds bigint not null,
+  -- how many matchups
+  n_matchups bigint not null,
+  -- is the tournament still visible
+  active bool not null
+);
+
+create view recent_tournament_data as
+  select td.* from tournament_data td
+  inner join (
+   select max(tournament_data_id) id 
+   from tournament_data 
+   group by tournament_id
+  ) maxids
+  on maxids.id = td.tournament_data_id;
+
+
+create table tournament_submission(
+  tournament_submission_id bigserial primary key,
+  creation_time bigint not null default extract(epoch from now()) * 1000,
+  creator_user_id bigint not null,
+  tournament_id bigint not null references tournament(tournament_id),
+  submission_id bigint not null references submission(submission_id),
+  name text not null,
+  kind bigint not null -- 0: Regular, 1: Validation, 2: Testcase, 3: Cancel
+);
+
+create view recent_tournament_submission as
+  select ts.* from tournament_submission ts
+  inner join (
+   select max(tournament_submission_id) id 
+   from tournament_submission 
+   group by tournament_id, submission_id
+  ) maxids
+  on maxids.id = ts.tournament_submission_id;
+
+
+-- a specific match resolution between two programs
+create table match_resolution (
+  match_resolution_id bigserial primary key,
+  creation_time bigint not null default extract(epoch from now()) * 1000,
+  submission_id bigint not null references submission(submission_id),
+  opponent_submission_id bigint not null references submission(submission_id),
+  -- which round in a matchup
+  round bigint not null,
+  -- which matchup it is
+  matchup bigint not null,
+  defected bool,
+  stdout text not null,
+  stderr text not null
+);
+
+create view recent_match_resolution as
+  select mr.* from match_resolution mr
+  inner join (
+   select max(match_resolution_id) id 
+   from match_resolution 
+   group by submission_id, opponent_submission_id, round, matchup
+  ) maxids
+  on maxids.id = mr.match_resolution_id;
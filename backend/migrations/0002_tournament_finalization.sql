@@ -0,0 +1,39 @@
+-- a frozen final evaluation of a tournament
+-- invariant: tournament_id is valid
+create table tournament_finalization(
+  tournament_finalization_id bigserial primary key,
+  creation_time bigint not null default extract(epoch from now()) * 1000,
+  creator_user_id bigint not null,
+  tournament_id bigint not null references tournament(tournament_id),
+  -- how many rounds in a matchup
+  n_rounds bigint not null,
+  -- how many matchups
+  n_matchups bigint not null,
+  -- a tournament can only be finalized once
+  unique (tournament_id)
+);
+
+-- the compete entries snapshotted when the tournament was finalized
+create table tournament_finalization_entry(
+  tournament_finalization_entry_id bigserial primary key,
+  creation_time bigint not null default extract(epoch from now()) * 1000,
+  tournament_finalization_id bigint not null references tournament_finalization(tournament_finalization_id),
+  tournament_submission_id bigint not null references tournament_submission(tournament_submission_id),
+  submission_id bigint not null references submission(submission_id),
+  name text not null
+);
+
+-- null for practice play, otherwise which final evaluation this belongs to
+-- null for every match played before tournaments could be finalized
+alter table match_resolution
+  add column tournament_finalization_id bigint references tournament_finalization(tournament_finalization_id);
+
+-- practice play and the final evaluation of the same round are different resolutions
+create or replace view recent_match_resolution as
+  select mr.* from match_resolution mr
+  inner join (
+   select max(match_resolution_id) id
+   from match_resolution
+   group by submission_id, opponent_submission_id, round, matchup, tournament_finalization_id
+  ) maxids
+  on maxids.id = mr.match_resolution_id;
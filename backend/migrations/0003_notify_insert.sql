@@ -0,0 +1,20 @@
+-- announce inserts so that every instance can forward them to its stream subscribers
+-- notifications are only delivered once the inserting transaction commits
+create or replace function notify_insert() returns trigger as $$
+begin
+  perform pg_notify(TG_TABLE_NAME || '_insert', (to_jsonb(new) ->> (TG_TABLE_NAME || '_id')));
+  return new;
+end;
+$$ language plpgsql;
+
+create trigger tournament_data_notify_insert
+  after insert on tournament_data
+  for each row execute function notify_insert();
+
+create trigger tournament_submission_notify_insert
+  after insert on tournament_submission
+  for each row execute function notify_insert();
+
+create trigger match_resolution_notify_insert
+  after insert on match_resolution
+  for each row execute function notify_insert();
@@ -0,0 +1,14 @@
+-- for Validation: enter the tournament as soon as all testcases pass
+-- false for entries made before auto compete existed
+alter table tournament_submission
+  add column auto_compete bool not null default false;
+
+-- the view's columns were fixed when it was created, so it has to be redefined to include the flag
+create or replace view recent_tournament_submission as
+  select ts.* from tournament_submission ts
+  inner join (
+   select max(tournament_submission_id) id
+   from tournament_submission
+   group by tournament_id, submission_id
+  ) maxids
+  on maxids.id = ts.tournament_submission_id;
@@ -0,0 +1,82 @@
+-- looking up the rounds of a matchup
+create index match_resolution_matchup_idx
+  on match_resolution(submission_id, opponent_submission_id, matchup, round);
+
+-- looking up a submission's entries
+create index tournament_submission_tournament_submission_idx
+  on tournament_submission(tournament_id, submission_id);
+
+create index tournament_data_tournament_idx
+  on tournament_data(tournament_id);
+
+-- the most recent resolution of each round
+-- invariant: match_resolution_id is the greatest id among resolutions of the same round
+-- maintained by a trigger on match_resolution, so it never has to be computed by grouping the whole table
+create table current_match_resolution(
+  submission_id bigint not null,
+  opponent_submission_id bigint not null,
+  matchup bigint not null,
+  round bigint not null,
+  -- null for practice play, otherwise which final evaluation this belongs to
+  tournament_finalization_id bigint,
+  match_resolution_id bigint not null references match_resolution(match_resolution_id)
+);
+
+-- practice play is keyed as -1, since nulls are distinct in unique indexes
+create unique index current_match_resolution_round_idx
+  on current_match_resolution(
+    submission_id,
+    opponent_submission_id,
+    matchup,
+    round,
+    coalesce(tournament_finalization_id, -1)
+  );
+
+create index current_match_resolution_opponent_idx
+  on current_match_resolution(opponent_submission_id);
+
+insert into current_match_resolution
+  select submission_id, opponent_submission_id, matchup, round, tournament_finalization_id, max(match_resolution_id)
+  from match_resolution
+  group by submission_id, opponent_submission_id, matchup, round, tournament_finalization_id;
+
+create function update_current_match_resolution() returns trigger as $$
+begin
+  insert into current_match_resolution(
+    submission_id,
+    opponent_submission_id,
+    matchup,
+    round,
+    tournament_finalization_id,
+    match_resolution_id
+  )
+  values (
+    new.submission_id,
+    new.opponent_submission_id,
+    new.matchup,
+    new.round,
+    new.tournament_finalization_id,
+    new.match_resolution_id
+  )
+  on conflict (
+    submission_id,
+    opponent_submission_id,
+    matchup,
+    round,
+    coalesce(tournament_finalization_id, -1)
+  )
+  -- concurrent inserts may commit out of order
+  do update set match_resolution_id = excluded.match_resolution_id
+  where current_match_resolution.match_resolution_id < excluded.match_resolution_id;
+  return new;
+end;
+$$ language plpgsql;
+
+create trigger match_resolution_update_current
+  after insert on match_resolution
+  for each row execute function update_current_match_resolution();
+
+create or replace view recent_match_resolution as
+  select mr.* from current_match_resolution cmr
+  inner join match_resolution mr
+  on mr.match_resolution_id = cmr.match_resolution_id;
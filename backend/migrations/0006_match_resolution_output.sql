@@ -0,0 +1,35 @@
+-- what a bot printed while resolving a match
+-- kept apart from match_resolution so that views don't have to read it, and so that it can be pruned
+-- invariant: match_resolution_id is valid
+-- output over the length cap is truncated, and the matching flag is set
+create table match_resolution_output(
+  match_resolution_id bigint primary key references match_resolution(match_resolution_id),
+  stdout text not null,
+  stdout_truncated bool not null,
+  stderr text not null,
+  stderr_truncated bool not null
+);
+
+-- existing output is capped as it's copied, at the default --max-output-length
+-- like new output, the start of stdout is kept, and the end of stderr where the traceback is
+-- the cap counts characters here rather than bytes, which only matters for output that isn't ascii
+insert into match_resolution_output
+  select
+    match_resolution_id,
+    left(stdout, 16384),
+    length(stdout) > 16384,
+    right(stderr, 16384),
+    length(stderr) > 16384
+  from match_resolution;
+
+drop view recent_match_resolution;
+
+-- the space is reclaimed by the next VACUUM FULL
+alter table match_resolution
+  drop column stdout,
+  drop column stderr;
+
+create view recent_match_resolution as
+  select mr.* from current_match_resolution cmr
+  inner join match_resolution mr
+  on mr.match_resolution_id = cmr.match_resolution_id;
@@ -0,0 +1,10 @@
+-- what python's random was seeded with, see harness::match_seed
+-- null for matches played before bots were seeded
+alter table match_resolution
+  add column seed bigint;
+
+-- the view's columns were fixed when it was created, so it has to be redefined to include the seed
+create or replace view recent_match_resolution as
+  select mr.* from current_match_resolution cmr
+  inner join match_resolution mr
+  on mr.match_resolution_id = cmr.match_resolution_id;
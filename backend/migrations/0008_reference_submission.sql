@@ -0,0 +1,18 @@
+-- system owned submissions that play a well known strategy, see strategies.rs
+-- they're created by the backend whenever it migrates, and a new one is added when a strategy's code changes
+-- invariant: submission_id is valid
+create table reference_submission(
+  reference_submission_id bigserial primary key,
+  creation_time bigint not null default extract(epoch from now()) * 1000,
+  submission_id bigint not null references submission(submission_id),
+  strategy bigint not null -- 0: AlwaysCooperate, 1: AlwaysDefect, 2: TitForTat, 3: GrimTrigger, 4: Random, 5: TitForTwoTats, 6: SuspiciousTitForTat
+);
+
+create view recent_reference_submission as
+  select rs.* from reference_submission rs
+  inner join (
+   select max(reference_submission_id) id
+   from reference_submission
+   group by strategy
+  ) maxids
+  on maxids.id = rs.reference_submission_id;
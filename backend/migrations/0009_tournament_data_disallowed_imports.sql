@@ -0,0 +1,14 @@
+-- modules that submissions entering the tournament may not import, see analysis::check_imports
+-- empty for tournaments created before the policy existed, which allow everything
+alter table tournament_data
+  add column disallowed_imports text[] not null default '{}';
+
+-- the view's columns were fixed when it was created, so it has to be redefined to include the policy
+create or replace view recent_tournament_data as
+  select td.* from tournament_data td
+  inner join (
+   select max(tournament_data_id) id
+   from tournament_data
+   group by tournament_id
+  ) maxids
+  on maxids.id = td.tournament_data_id;
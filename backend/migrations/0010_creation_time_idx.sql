@@ -0,0 +1,7 @@
+-- streams catch up on what they missed by creation time rather than by id, see handlers::StreamFence
+create index match_resolution_creation_time_idx
+  on match_resolution(creation_time);
+create index tournament_submission_creation_time_idx
+  on tournament_submission(creation_time);
+create index tournament_data_creation_time_idx
+  on tournament_data(creation_time);
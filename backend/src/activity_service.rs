@@ -0,0 +1,24 @@
+use tokio_postgres::GenericClient;
+
+// The creation time that rows which haven't been committed yet will have at least.
+// Rows are created at the start of the transaction that inserts them,
+// so this is the start of the oldest transaction still open, or now if there isn't one.
+// Rounded down a millisecond, since creation times are rounded to one.
+pub async fn get_oldest_open_creation_time(
+    con: &mut impl GenericClient,
+) -> Result<i64, tokio_postgres::Error> {
+    let row = con
+        .query_one(
+            "SELECT
+             (extract(epoch from least(min(xact_start), now())) * 1000)::bigint - 1
+             FROM pg_stat_activity
+             WHERE datname = current_database()
+             AND pid <> pg_backend_pid()
+             AND xact_start IS NOT NULL
+            ",
+            &[],
+        )
+        .await?;
+
+    Ok(row.get(0))
+}
@@ -0,0 +1,432 @@
+use super::response;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Parses a submission with python's own parser, in the sandbox, without running it.
+// Prints what check needs to know as json, see Analysis.
+static ANALYZE_SCRIPT: &str = r#"#!/usr/bin/env python3
+import ast
+import json
+import sys
+
+
+def binds_should_defect(node):
+    # whether node binds should_defect in the module's scope, however deep in ifs, trys, withs and loops it is
+    if isinstance(node, (ast.FunctionDef, ast.AsyncFunctionDef, ast.ClassDef)):
+        return node.name == "should_defect"
+    if isinstance(node, ast.Lambda):
+        return False
+    if isinstance(node, ast.Name):
+        return node.id == "should_defect" and isinstance(node.ctx, ast.Store)
+    if isinstance(node, ast.alias):
+        return (node.asname or node.name.split(".")[0]) == "should_defect"
+    return any(binds_should_defect(child) for child in ast.iter_child_nodes(node))
+
+
+def analyze(source):
+    result = {"syntaxError": None, "shouldDefect": None, "imports": []}
+
+    try:
+        tree = ast.parse(source, "submission.py")
+    except (SyntaxError, ValueError) as e:
+        result["syntaxError"] = {
+            "line": getattr(e, "lineno", None),
+            "column": getattr(e, "offset", None),
+            "message": getattr(e, "msg", str(e)),
+        }
+        return result
+
+    # the last top level binding of should_defect is the one the harness calls
+    for node in tree.body:
+        if isinstance(node, (ast.FunctionDef, ast.AsyncFunctionDef)) and node.name == "should_defect":
+            args = node.args
+            positional = getattr(args, "posonlyargs", []) + args.args
+            result["shouldDefect"] = {
+                "line": node.lineno,
+                "function": True,
+                "isAsync": isinstance(node, ast.AsyncFunctionDef),
+                "minArgs": len(positional) - len(args.defaults),
+                "maxArgs": None if args.vararg else len(positional),
+                "requiredKeywordOnly": any(d is None for d in args.kw_defaults),
+            }
+        elif binds_should_defect(node):
+            # assigned, imported, or only defined under some condition,
+            # so its signature can't be known without running it
+            result["shouldDefect"] = {"line": node.lineno, "function": False}
+
+    for node in ast.walk(tree):
+        if isinstance(node, ast.Import):
+            for alias in node.names:
+                result["imports"].append({"module": alias.name, "line": node.lineno})
+        elif isinstance(node, ast.ImportFrom):
+            # relative imports can only refer to the submission's own files
+            if node.level == 0 and node.module:
+                result["imports"].append({"module": node.module, "line": node.lineno})
+        elif isinstance(node, ast.Call) and node.args:
+            func = node.func
+            name = func.id if isinstance(func, ast.Name) else func.attr if isinstance(func, ast.Attribute) else None
+            arg = node.args[0]
+            if name in ("__import__", "import_module") and isinstance(arg, ast.Constant) and isinstance(arg.value, str):
+                result["imports"].append({"module": arg.value, "line": node.lineno})
+
+    return result
+
+
+with open("submission.py", encoding="utf-8", errors="replace") as f:
+    source = f.read()
+
+try:
+    result = analyze(source)
+except Exception as e:
+    # such as a RecursionError on deeply nested code, in which case nothing is printed and the code goes unanalyzed
+    print("could not analyze submission: {!r}".format(e), file=sys.stderr)
+    sys.exit(1)
+
+print(json.dumps(result))
+"#;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyntaxError {
+    pub line: Option<i64>,
+    pub column: Option<i64>,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShouldDefect {
+    pub line: i64,
+    // false if should_defect is assigned, imported or defined conditionally rather than defined outright
+    pub function: bool,
+    #[serde(default)]
+    pub is_async: bool,
+    #[serde(default)]
+    pub min_args: i64,
+    // None if it takes *args
+    #[serde(default)]
+    pub max_args: Option<i64>,
+    #[serde(default)]
+    pub required_keyword_only: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Import {
+    pub module: String,
+    pub line: i64,
+}
+
+// what the analysis script found out about a submission
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Analysis {
+    pub syntax_error: Option<SyntaxError>,
+    // None if should_defect isn't defined at the top level
+    pub should_defect: Option<ShouldDefect>,
+    pub imports: Vec<Import>,
+}
+
+// the files run in the sandbox to analyze the code, which print the Analysis
+pub fn analysis_files(code: &str) -> HashMap<String, String> {
+    [
+        (String::from("run"), String::from(ANALYZE_SCRIPT)),
+        (String::from("submission.py"), String::from(code)),
+    ]
+    .into_iter()
+    .collect()
+}
+
+fn n_arguments(n: i64) -> String {
+    match n {
+        1 => String::from("1 argument"),
+        n => format!("{} arguments", n),
+    }
+}
+
+fn code_error(
+    code: response::AppError,
+    line: Option<i64>,
+    column: Option<i64>,
+    message: String,
+) -> response::ErrorResponse {
+    response::ErrorResponse::with_details(
+        code,
+        response::ErrorDetails::Code {
+            line,
+            column,
+            message,
+        },
+    )
+}
+
+// Rejects code that would fail every round: syntax errors,
+// and a should_defect that can't be called with the opponent's should_defect and their history.
+// Code the analysis couldn't make sense of is let through, and left to fail its testcases if it's broken.
+pub fn check_callable(analysis: Option<&Analysis>) -> Result<(), response::ErrorResponse> {
+    let analysis = match analysis {
+        Some(x) => x,
+        None => return Ok(()),
+    };
+
+    if let Some(syntax_error) = &analysis.syntax_error {
+        return Err(code_error(
+            response::AppError::SubmissionSyntaxError,
+            syntax_error.line,
+            syntax_error.column,
+            syntax_error.message.clone(),
+        ));
+    }
+
+    let should_defect = match &analysis.should_defect {
+        Some(x) => x,
+        None => {
+            return Err(code_error(
+                response::AppError::SubmissionShouldDefectInvalid,
+                None,
+                None,
+                String::from("define a function should_defect(opp_defection_function, opponent_defection_history) at the top level"),
+            ))
+        }
+    };
+
+    // we can only check functions that are defined in the code
+    if !should_defect.function {
+        return Ok(());
+    }
+
+    let message = if should_defect.is_async {
+        Some(String::from(
+            "should_defect must be a regular function, not async",
+        ))
+    } else if should_defect.min_args > 2 {
+        Some(format!(
+            "should_defect requires {}, but is called with 2: the opponent's should_defect and their defection history",
+            n_arguments(should_defect.min_args)
+        ))
+    } else if should_defect.max_args.is_some_and(|x| x < 2) {
+        Some(format!(
+            "should_defect takes {}, but is called with 2: the opponent's should_defect and their defection history",
+            n_arguments(should_defect.max_args.unwrap_or(0))
+        ))
+    } else if should_defect.required_keyword_only {
+        Some(String::from(
+            "should_defect has keyword only arguments without defaults, which it is never called with",
+        ))
+    } else {
+        None
+    };
+
+    match message {
+        Some(message) => Err(code_error(
+            response::AppError::SubmissionShouldDefectInvalid,
+            Some(should_defect.line),
+            None,
+            message,
+        )),
+        None => Ok(()),
+    }
+}
+
+// Rejects code that imports a module the tournament disallows, or a submodule of one.
+// This only catches plain imports: the sandbox, not this, is what keeps bots contained.
+// Unlike check_callable, code the analysis couldn't make sense of is rejected,
+// since otherwise tripping up the analysis would be a way around the policy.
+pub fn check_imports(
+    analysis: Option<&Analysis>,
+    disallowed_imports: &[String],
+) -> Result<(), response::ErrorResponse> {
+    let analysis = match analysis {
+        Some(x) => x,
+        None => {
+            return Err(code_error(
+                response::AppError::SubmissionImportDisallowed,
+                None,
+                None,
+                String::from("the submission's imports couldn't be checked against the ones this tournament disallows"),
+            ))
+        }
+    };
+
+    for import in &analysis.imports {
+        let disallowed = disallowed_imports
+            .iter()
+            .any(|x| import.module == *x || import.module.starts_with(&format!("{}.", x)));
+        if disallowed {
+            return Err(code_error(
+                response::AppError::SubmissionImportDisallowed,
+                Some(import.line),
+                None,
+                format!(
+                    "importing {} isn't allowed in this tournament, which disallows {}",
+                    import.module,
+                    disallowed_imports.join(", ")
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+// a module name as written in an import statement, such as os or os.path
+pub fn is_module_name(name: &str) -> bool {
+    name.split('.').all(|part| {
+        part.chars()
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_')
+            && part.chars().all(|c| c.is_alphanumeric() || c == '_')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use response::AppError;
+
+    fn should_defect(min_args: i64, max_args: Option<i64>) -> ShouldDefect {
+        ShouldDefect {
+            line: 1,
+            function: true,
+            is_async: false,
+            min_args,
+            max_args,
+            required_keyword_only: false,
+        }
+    }
+
+    fn analysis(should_defect: Option<ShouldDefect>, imports: &[&str]) -> Analysis {
+        Analysis {
+            syntax_error: None,
+            should_defect,
+            imports: imports
+                .iter()
+                .map(|x| Import {
+                    module: String::from(*x),
+                    line: 1,
+                })
+                .collect(),
+        }
+    }
+
+    fn code(result: Result<(), response::ErrorResponse>) -> Option<AppError> {
+        result.err().map(|x| x.code)
+    }
+
+    #[test]
+    fn check_callable_accepts_two_arguments() {
+        for (min_args, max_args) in [
+            (2, Some(2)),
+            (1, Some(2)),
+            (0, Some(3)),
+            (2, None),
+            (0, None),
+        ] {
+            let x = analysis(Some(should_defect(min_args, max_args)), &[]);
+            assert!(
+                check_callable(Some(&x)).is_ok(),
+                "{} {:?}",
+                min_args,
+                max_args
+            );
+        }
+    }
+
+    #[test]
+    fn check_callable_rejects_the_wrong_arity() {
+        for (min_args, max_args) in [(3, Some(3)), (3, None), (1, Some(1)), (0, Some(0))] {
+            let x = analysis(Some(should_defect(min_args, max_args)), &[]);
+            assert!(
+                matches!(
+                    code(check_callable(Some(&x))),
+                    Some(AppError::SubmissionShouldDefectInvalid)
+                ),
+                "{} {:?}",
+                min_args,
+                max_args
+            );
+        }
+    }
+
+    #[test]
+    fn check_callable_rejects_async_and_keyword_only() {
+        let mut x = should_defect(2, Some(2));
+        x.is_async = true;
+        assert!(check_callable(Some(&analysis(Some(x), &[]))).is_err());
+
+        let mut x = should_defect(2, Some(2));
+        x.required_keyword_only = true;
+        assert!(check_callable(Some(&analysis(Some(x), &[]))).is_err());
+    }
+
+    #[test]
+    fn check_callable_rejects_missing_should_defect_and_syntax_errors() {
+        assert!(matches!(
+            code(check_callable(Some(&analysis(None, &[])))),
+            Some(AppError::SubmissionShouldDefectInvalid)
+        ));
+
+        let mut x = analysis(Some(should_defect(2, Some(2))), &[]);
+        x.syntax_error = Some(SyntaxError {
+            line: Some(1),
+            column: Some(1),
+            message: String::from("invalid syntax"),
+        });
+        assert!(matches!(
+            code(check_callable(Some(&x))),
+            Some(AppError::SubmissionSyntaxError)
+        ));
+    }
+
+    #[test]
+    fn check_callable_lets_through_what_it_cant_check() {
+        assert!(check_callable(None).is_ok());
+
+        // assigned rather than defined, so its arguments are unknown
+        let mut x = should_defect(5, Some(5));
+        x.function = false;
+        assert!(check_callable(Some(&analysis(Some(x), &[]))).is_ok());
+    }
+
+    #[test]
+    fn check_imports_rejects_disallowed_modules_and_submodules() {
+        let disallowed = [String::from("os"), String::from("urllib.request")];
+        let check = |imports: &[&str]| {
+            code(check_imports(
+                Some(&analysis(Some(should_defect(2, Some(2))), imports)),
+                &disallowed,
+            ))
+        };
+        assert!(check(&[]).is_none());
+        assert!(check(&["random", "osmosis", "urllib", "urllib.parse"]).is_none());
+        assert!(matches!(
+            check(&["os"]),
+            Some(AppError::SubmissionImportDisallowed)
+        ));
+        assert!(matches!(
+            check(&["os.path"]),
+            Some(AppError::SubmissionImportDisallowed)
+        ));
+        assert!(matches!(
+            check(&["urllib.request"]),
+            Some(AppError::SubmissionImportDisallowed)
+        ));
+    }
+
+    #[test]
+    fn check_imports_rejects_what_it_cant_check() {
+        assert!(matches!(
+            code(check_imports(None, &[String::from("os")])),
+            Some(AppError::SubmissionImportDisallowed)
+        ));
+    }
+
+    #[test]
+    fn module_names() {
+        for x in ["os", "os.path", "_thread", "urllib.request", "módulo"] {
+            assert!(is_module_name(x), "{}", x);
+        }
+        for x in ["", "os.", ".os", "os..path", "1os", "os path", "os-path"] {
+            assert!(!is_module_name(x), "{}", x);
+        }
+    }
+}
@@ -1,12 +1,19 @@
 use crate::AppData;
+use crate::Limits;
 
 use super::handlers;
+use super::openapi::OpenApi;
+use super::request;
 use super::response;
 use super::response::AppError;
 use super::utils;
 use super::SERVICE_NAME;
+use std::collections::HashMap;
 use std::convert::Infallible;
 use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use warp::http::StatusCode;
 use warp::ws::WebSocket;
 use warp::Filter;
@@ -25,13 +32,19 @@ macro_rules! combine {
 /// The function that will show all ones to call
 pub fn api(app_data:AppData) -> impl Filter<Extract = (impl warp::Reply,), Error = Infallible> + Clone {
     // public API
-    combine!(
+    rate_limit(&app_data.limits).and(combine!(
         api_info(),
+        api_openapi(),
         adapter(
             app_data.clone(),
             warp::path!("public" / "submission" / "new"),
             handlers::submission_new,
         ),
+        adapter(
+            app_data.clone(),
+            warp::path!("public" / "submission" / "test_run"),
+            handlers::submission_test_run,
+        ),
         adapter(
             app_data.clone(),
             warp::path!("public" / "tournament" / "new"),
@@ -47,11 +60,31 @@ pub fn api(app_data:AppData) -> impl Filter<Extract = (impl warp::Reply,), Error
             warp::path!("public" / "tournament_submission" / "new"),
             handlers::tournament_submission_new,
         ),
+        adapter(
+            app_data.clone(),
+            warp::path!("public" / "tournament_submission" / "new_reference"),
+            handlers::tournament_submission_new_reference,
+        ),
+        adapter(
+            app_data.clone(),
+            warp::path!("public" / "tournament_submission" / "validation_report"),
+            handlers::tournament_submission_validation_report,
+        ),
+        adapter(
+            app_data.clone(),
+            warp::path!("public" / "tournament_finalization" / "new"),
+            handlers::tournament_finalization_new,
+        ),
         adapter(
             app_data.clone(),
             warp::path!("public" / "submission" / "view"),
             handlers::submission_view,
         ),
+        adapter(
+            app_data.clone(),
+            warp::path!("public" / "reference_submission" / "view"),
+            handlers::reference_submission_view,
+        ),
         adapter(
             app_data.clone(),
             warp::path!("public" / "tournament_data" / "view"),
@@ -67,17 +100,107 @@ pub fn api(app_data:AppData) -> impl Filter<Extract = (impl warp::Reply,), Error
             warp::path!("public" / "match_resolution" / "view"),
             handlers::match_resolution_view,
         ),
+        adapter(
+            app_data.clone(),
+            warp::path!("public" / "match_resolution" / "output"),
+            handlers::match_resolution_output,
+        ),
+        adapter(
+            app_data.clone(),
+            warp::path!("public" / "tournament" / "detail"),
+            handlers::tournament_detail,
+        ),
+        adapter(
+            app_data.clone(),
+            warp::path!("public" / "tournament" / "export"),
+            handlers::tournament_export,
+        ),
+        adapter(
+            app_data.clone(),
+            warp::path!("public" / "tournament_finalization" / "view"),
+            handlers::tournament_finalization_view,
+        ),
+        adapter(
+            app_data.clone(),
+            warp::path!("public" / "tournament_finalization_entry" / "view"),
+            handlers::tournament_finalization_entry_view,
+        ),
         ws_adapter(
             app_data.clone(),
             warp::path!("public" / "ws" / "match_resolution_lite" / "stream"),
             handlers::match_resolution_lite_stream,
         ),
+        ws_adapter(
+            app_data.clone(),
+            warp::path!("public" / "ws" / "tournament_data" / "stream"),
+            handlers::tournament_data_stream,
+        ),
         ws_adapter(
             app_data.clone(),
             warp::path!("public" / "ws" / "tournament_submission" / "stream"),
             handlers::tournament_submission_stream,
+        ),
+        ws_adapter(
+            app_data.clone(),
+            warp::path!("public" / "ws" / "tournament" / "standings" / "stream"),
+            handlers::tournament_standings_stream,
+        ),
+        get_adapter(
+            app_data.clone(),
+            warp::path!("public" / "v1" / "tournaments").map(|| ()),
+            handlers::v1_tournaments,
+        ),
+        get_adapter(
+            app_data.clone(),
+            warp::path!("public" / "v1" / "tournaments" / i64),
+            handlers::v1_tournament,
+        ),
+        get_adapter(
+            app_data.clone(),
+            warp::path!("public" / "v1" / "tournaments" / i64 / "data"),
+            handlers::v1_tournament_data,
+        ),
+        get_adapter(
+            app_data.clone(),
+            warp::path!("public" / "v1" / "tournaments" / i64 / "submissions"),
+            handlers::v1_tournament_submissions,
+        ),
+        download_adapter(
+            app_data.clone(),
+            warp::path!("public" / "v1" / "tournaments" / i64 / "results"),
+            handlers::v1_tournament_results,
+        ),
+        get_adapter(
+            app_data.clone(),
+            warp::path!("public" / "v1" / "submissions" / i64),
+            handlers::v1_submission,
+        ),
+        get_adapter(
+            app_data.clone(),
+            warp::path!("public" / "v1" / "match_resolutions").map(|| ()),
+            handlers::v1_match_resolutions,
+        ),
+        get_adapter(
+            app_data.clone(),
+            warp::path!("public" / "v1" / "match_resolutions" / i64 / "output"),
+            handlers::v1_match_resolution_output,
+        ),
+        sse_adapter(
+            app_data.clone(),
+            warp::path!("public" / "sse" / "match_resolution_lite" / "stream"),
+            handlers::match_resolution_lite_event_stream,
+        ),
+        sse_adapter(
+            app_data.clone(),
+            warp::path!("public" / "sse" / "tournament_data" / "stream"),
+            handlers::tournament_data_event_stream,
+        ),
+        sse_adapter(
+            app_data.clone(),
+            warp::path!("public" / "sse" / "tournament_submission" / "stream"),
+            handlers::tournament_submission_event_stream,
         )
-    )
+    ))
     .recover(handle_rejection)
 }
 
@@ -91,15 +214,147 @@ fn api_info() -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Reject
     warp::path!("public" / "info").map(move || warp::reply::json(&info))
 }
 
+fn api_openapi() -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    let document = openapi();
+    warp::path!("public" / "openapi.json").map(move || warp::reply::json(&document))
+}
+
+// describes the routes above; keep this in sync when adding a route
+pub fn openapi() -> serde_json::Value {
+    OpenApi::new()
+        .post("/public/submission/new", handlers::submission_new)
+        .post("/public/submission/test_run", handlers::submission_test_run)
+        .post("/public/tournament/new", handlers::tournament_new)
+        .post("/public/tournament_data/new", handlers::tournament_data_new)
+        .post("/public/tournament_submission/new", handlers::tournament_submission_new)
+        .post("/public/tournament_submission/new_reference", handlers::tournament_submission_new_reference)
+        .post("/public/tournament_submission/validation_report", handlers::tournament_submission_validation_report)
+        .post("/public/tournament_finalization/new", handlers::tournament_finalization_new)
+        .post("/public/submission/view", handlers::submission_view)
+        .post("/public/reference_submission/view", handlers::reference_submission_view)
+        .post("/public/tournament_data/view", handlers::tournament_data_view)
+        .post("/public/tournament_submission/view", handlers::tournament_submission_view)
+        .post("/public/match_resolution/view", handlers::match_resolution_view)
+        .post("/public/match_resolution/output", handlers::match_resolution_output)
+        .post("/public/tournament/detail", handlers::tournament_detail)
+        .post("/public/tournament/export", handlers::tournament_export)
+        .post("/public/tournament_finalization/view", handlers::tournament_finalization_view)
+        .post("/public/tournament_finalization_entry/view", handlers::tournament_finalization_entry_view)
+        .ws::<request::MatchResolutionViewProps, response::MatchResolutionLite>("/public/ws/match_resolution_lite/stream")
+        .ws::<request::TournamentDataViewProps, response::TournamentData>("/public/ws/tournament_data/stream")
+        .ws::<request::TournamentSubmissionViewProps, response::TournamentSubmission>("/public/ws/tournament_submission/stream")
+        .ws::<request::TournamentStandingsStreamProps, response::Standing>("/public/ws/tournament/standings/stream")
+        .get("/public/v1/tournaments", handlers::v1_tournaments)
+        .get("/public/v1/tournaments/{tournamentId}", handlers::v1_tournament)
+        .get("/public/v1/tournaments/{tournamentId}/data", handlers::v1_tournament_data)
+        .get("/public/v1/tournaments/{tournamentId}/submissions", handlers::v1_tournament_submissions)
+        .download("/public/v1/tournaments/{tournamentId}/results", handlers::v1_tournament_results)
+        .get("/public/v1/submissions/{submissionId}", handlers::v1_submission)
+        .get("/public/v1/match_resolutions", handlers::v1_match_resolutions)
+        .get("/public/v1/match_resolutions/{matchResolutionId}/output", handlers::v1_match_resolution_output)
+        .sse("/public/sse/match_resolution_lite/stream", handlers::match_resolution_lite_event_stream)
+        .sse("/public/sse/tournament_data/stream", handlers::tournament_data_event_stream)
+        .sse("/public/sse/tournament_submission/stream", handlers::tournament_submission_event_stream)
+        .build(SERVICE_NAME, env!("CARGO_PKG_VERSION"))
+}
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+// the address a request came from
+// X-Forwarded-For is only believed when a trusted proxy sent it, since clients can write anything there
+// each proxy appends the address it got the request from, so the client is the last one that isn't a trusted proxy
+fn client_address(
+    trusted_proxies: &[IpAddr],
+    forwarded_for: Option<String>,
+    remote: Option<SocketAddr>,
+) -> String {
+    let remote = match remote {
+        Some(x) => x.ip(),
+        None => return String::new(),
+    };
+
+    if !trusted_proxies.contains(&remote) {
+        return remote.to_string();
+    }
+
+    forwarded_for
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .rev()
+        .find(|x| !matches!(x.parse::<IpAddr>(), Ok(ip) if trusted_proxies.contains(&ip)))
+        .map(str::to_owned)
+        .unwrap_or_else(|| remote.to_string())
+}
+
+// rejects requests once a client has used up its token bucket
+// clients are identified by client_address
+fn rate_limit(limits: &Limits) -> impl Filter<Extract = (), Error = warp::Rejection> + Clone {
+    let requests_per_second = limits.requests_per_second;
+    let request_burst = limits.request_burst;
+    let trusted_proxies = Arc::new(limits.trusted_proxies.clone());
+    let buckets: Arc<Mutex<HashMap<String, TokenBucket>>> = Arc::new(Mutex::new(HashMap::new()));
+
+    warp::header::optional::<String>("x-forwarded-for")
+        .and(warp::addr::remote())
+        .and_then(
+            move |forwarded_for: Option<String>, remote: Option<SocketAddr>| {
+                let buckets = buckets.clone();
+                let trusted_proxies = trusted_proxies.clone();
+                async move {
+                    let client = client_address(&trusted_proxies, forwarded_for, remote);
+
+                    let now = Instant::now();
+                    let mut buckets = buckets.lock().unwrap();
+
+                    // forget clients whose buckets would have refilled anyway
+                    if buckets.len() > 1024 {
+                        buckets.retain(|_, b| {
+                            now.duration_since(b.last_refill).as_secs_f64() * requests_per_second
+                                < request_burst
+                        });
+                    }
+
+                    let bucket = buckets.entry(client).or_insert(TokenBucket {
+                        tokens: request_burst,
+                        last_refill: now,
+                    });
+
+                    // refill the bucket for the time elapsed since the last request
+                    bucket.tokens = f64::min(
+                        request_burst,
+                        bucket.tokens
+                            + now.duration_since(bucket.last_refill).as_secs_f64()
+                                * requests_per_second,
+                    );
+                    bucket.last_refill = now;
+
+                    if bucket.tokens < 1.0 {
+                        return Err(app_error(AppError::RateLimited));
+                    }
+                    bucket.tokens -= 1.0;
+
+                    Ok(())
+                }
+            },
+        )
+        .untuple_one()
+}
+
 // this function adapts a handler function to a warp filter
 // it accepts an initial path filter
-fn adapter<PropsType, ResponseType, F>(
+fn adapter<PropsType, ResponseType, ErrorType, F>(
     app_data: AppData,
     filter: impl Filter<Extract = (), Error = warp::Rejection> + Clone,
     handler: fn(AppData, PropsType) -> F,
 ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone
 where
-    F: Future<Output = Result<ResponseType, AppError>> + Send,
+    F: Future<Output = Result<ResponseType, ErrorType>> + Send,
+    ErrorType: Into<response::ErrorResponse>,
     PropsType: Send + serde::de::DeserializeOwned,
     ResponseType: Send + serde::ser::Serialize,
 {
@@ -121,6 +376,97 @@ where
         .map(|x| warp::reply::json(&x))
 }
 
+// this function adapts a GET handler function to a warp filter
+// it accepts an initial path filter, which may extract a path parameter,
+// reads the props from the query string, and the api key from an `Authorization: Bearer` header
+fn get_adapter<ParamsType, PropsType, ResponseType, F>(
+    app_data: AppData,
+    filter: impl Filter<Extract = (ParamsType,), Error = warp::Rejection> + Clone,
+    handler: fn(AppData, ParamsType, PropsType, String) -> F,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone
+where
+    F: Future<Output = Result<ResponseType, AppError>> + Send,
+    ParamsType: Send,
+    PropsType: Send + serde::de::DeserializeOwned + 'static,
+    ResponseType: Send + serde::ser::Serialize,
+{
+    // lets you pass in an arbitrary parameter
+    fn with<T: Clone + Send>(t: T) -> impl Filter<Extract = (T,), Error = Infallible> + Clone {
+        warp::any().map(move || t.clone())
+    }
+
+    filter
+        .and(warp::get())
+        .and(with(app_data))
+        .and(warp::query::<PropsType>())
+        .and(warp::header::optional::<String>("authorization"))
+        .and_then(
+            move |params, app_data, props, authorization: Option<String>| async move {
+                // anonymous requests get an empty api key, which only works for public views
+                let api_key = authorization
+                    .as_deref()
+                    .and_then(|x| x.strip_prefix("Bearer "))
+                    .unwrap_or_default()
+                    .trim()
+                    .to_owned();
+                handler(app_data, params, props, api_key)
+                    .await
+                    .map_err(app_error)
+            },
+        )
+        .map(|x| warp::reply::json(&x))
+}
+
+// like get_adapter, but the handler's file is streamed back instead of being serialized as json
+fn download_adapter<ParamsType, PropsType, F>(
+    app_data: AppData,
+    filter: impl Filter<Extract = (ParamsType,), Error = warp::Rejection> + Clone,
+    handler: fn(AppData, ParamsType, PropsType, String) -> F,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone
+where
+    F: Future<Output = Result<handlers::ResultsDownload, AppError>> + Send,
+    ParamsType: Send,
+    PropsType: Send + serde::de::DeserializeOwned + 'static,
+{
+    // lets you pass in an arbitrary parameter
+    fn with<T: Clone + Send>(t: T) -> impl Filter<Extract = (T,), Error = Infallible> + Clone {
+        warp::any().map(move || t.clone())
+    }
+
+    filter
+        .and(warp::get())
+        .and(with(app_data))
+        .and(warp::query::<PropsType>())
+        .and(warp::header::optional::<String>("authorization"))
+        .and_then(
+            move |params, app_data, props, authorization: Option<String>| async move {
+                let api_key = authorization
+                    .as_deref()
+                    .and_then(|x| x.strip_prefix("Bearer "))
+                    .unwrap_or_default()
+                    .trim()
+                    .to_owned();
+                handler(app_data, params, props, api_key)
+                    .await
+                    .map_err(app_error)
+            },
+        )
+        .map(|download: handlers::ResultsDownload| {
+            let content_type = match download.format {
+                request::ResultsFormat::Csv => "text/csv; charset=utf-8",
+                request::ResultsFormat::Ndjson => "application/x-ndjson",
+            };
+            warp::http::Response::builder()
+                .header("content-type", content_type)
+                .header(
+                    "content-disposition",
+                    format!("attachment; filename=\"{}\"", download.filename),
+                )
+                .body(warp::hyper::Body::wrap_stream(download.lines))
+                .expect("response should have been built")
+        })
+}
+
 // this function adapts a handler function to a warp filter
 // it accepts an initial path filter
 fn ws_adapter<F>(
@@ -146,27 +492,54 @@ where
         })
 }
 
+// this function adapts a server-sent events handler function to a warp filter
+// it accepts an initial path filter, and reads the props from the query string
+fn sse_adapter<PropsType, F>(
+    app_data: AppData,
+    filter: impl Filter<Extract = (), Error = warp::Rejection> + Clone,
+    handler: fn(AppData, PropsType, Option<i64>) -> F,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone
+where
+    F: Future<Output = Result<handlers::EventStream, AppError>> + Send,
+    PropsType: Send + serde::de::DeserializeOwned + 'static,
+{
+    // lets you pass in an arbitrary parameter
+    fn with<T: Clone + Send>(t: T) -> impl Filter<Extract = (T,), Error = Infallible> + Clone {
+        warp::any().map(move || t.clone())
+    }
+
+    filter
+        .and(warp::get())
+        .and(with(app_data))
+        .and(warp::query::<PropsType>())
+        .and(warp::header::optional::<i64>("last-event-id"))
+        .and_then(
+            move |app_data, props, last_event_id| async move {
+                handler(app_data, props, last_event_id)
+                    .await
+                    .map_err(app_error)
+            },
+        )
+        .map(|stream| warp::sse::reply(warp::sse::keep_alive().stream(stream)))
+}
+
 // This function receives a `Rejection` and tries to return a custom
 // value, otherwise simply passes the rejection along.
 async fn handle_rejection(err: warp::Rejection) -> Result<impl warp::Reply, Infallible> {
-    let code;
-    let message;
+    let message: response::ErrorResponse;
 
     if err.is_not_found() {
-        code = StatusCode::NOT_FOUND;
-        message = AppError::NotFound;
+        message = AppError::NotFound.into();
     } else if err
         .find::<warp::filters::body::BodyDeserializeError>()
         .is_some()
+        || err.find::<warp::reject::InvalidQuery>().is_some()
     {
-        message = AppError::DecodeError;
-        code = StatusCode::BAD_REQUEST;
+        message = AppError::DecodeError.into();
     } else if err.find::<warp::reject::MethodNotAllowed>().is_some() {
-        code = StatusCode::METHOD_NOT_ALLOWED;
-        message = AppError::MethodNotAllowed;
-    } else if let Some(AppErrorRejection(app_error)) = err.find() {
-        code = StatusCode::BAD_REQUEST;
-        message = app_error.clone();
+        message = AppError::MethodNotAllowed.into();
+    } else if let Some(AppErrorRejection(error_response)) = err.find() {
+        message = error_response.clone();
     } else {
         // We should have expected this... Just log and say its a 500
         utils::log(utils::Event {
@@ -174,19 +547,63 @@ async fn handle_rejection(err: warp::Rejection) -> Result<impl warp::Reply, Infa
             source: format!("{:#?}", err),
             severity: utils::SeverityKind::Error,
         });
-        code = StatusCode::INTERNAL_SERVER_ERROR;
-        message = AppError::Unknown;
+        message = AppError::Unknown.into();
     }
 
+    let code = status_code(&message.code);
+
     Ok(warp::reply::with_status(warp::reply::json(&message), code))
 }
 
+fn status_code(app_error: &AppError) -> StatusCode {
+    match app_error {
+        AppError::Unauthorized => StatusCode::UNAUTHORIZED,
+        AppError::NoCapability => StatusCode::FORBIDDEN,
+        AppError::NotFound
+        | AppError::SubmissionNonexistent
+        | AppError::TournamentNonexistent
+        | AppError::TournamentFinalizationNonexistent
+        | AppError::MatchResolutionNonexistent => StatusCode::NOT_FOUND,
+        // the output existed, but won't again
+        AppError::MatchResolutionOutputPruned => StatusCode::GONE,
+        AppError::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+        // the request is fine, but conflicts with the current state of the tournament
+        AppError::TournamentArchived
+        | AppError::TournamentFinalized
+        | AppError::TournamentSubmissionCompeteLimit
+        | AppError::TournamentSubmissionNotValidated
+        | AppError::TournamentSubmissionTestcaseIncomplete
+        | AppError::TournamentSubmissionTestcaseFails
+        | AppError::SubmissionImportDisallowed => StatusCode::CONFLICT,
+        AppError::RateLimited
+        | AppError::SubmissionRateLimited
+        | AppError::MatchBudgetExceeded => StatusCode::TOO_MANY_REQUESTS,
+        AppError::InternalServerError | AppError::Unknown => StatusCode::INTERNAL_SERVER_ERROR,
+        AppError::Network => StatusCode::BAD_GATEWAY,
+        AppError::TournamentDataNRoundsInvalid
+        | AppError::TournamentDataNMatchupsInvalid
+        | AppError::TournamentDataTooManyMatches
+        | AppError::TournamentDataDisallowedImportInvalid
+        | AppError::SubmissionTooLong
+        | AppError::SubmissionSyntaxError
+        | AppError::SubmissionShouldDefectInvalid
+        | AppError::TestRunNRoundsInvalid
+        | AppError::TestRunOpponentInvalid
+        | AppError::ArchiveVersionUnsupported
+        | AppError::ArchiveInvalid
+        | AppError::StreamEndBeforeRequest
+        | AppError::CursorInvalid
+        | AppError::DecodeError
+        | AppError::BadRequest => StatusCode::BAD_REQUEST,
+    }
+}
+
 // This type represents errors that we can generate
-// These will be automatically converted to a proper string later
+// These will be automatically converted to a proper response later
 #[derive(Debug)]
-pub struct AppErrorRejection(pub AppError);
+pub struct AppErrorRejection(pub response::ErrorResponse);
 impl warp::reject::Reject for AppErrorRejection {}
 
-fn app_error(app_error: AppError) -> warp::reject::Rejection {
-    warp::reject::custom(AppErrorRejection(app_error))
+fn app_error(app_error: impl Into<response::ErrorResponse>) -> warp::reject::Rejection {
+    warp::reject::custom(AppErrorRejection(app_error.into()))
 }
@@ -0,0 +1,97 @@
+use super::db_types::*;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// bump whenever the layout of an archive, or of the rows in it, changes
+// older archives can still be imported, as long as the new fields have defaults
+// 2: match resolutions have a seed
+// 3: tournament data has disallowed imports
+pub static ARCHIVE_VERSION: i64 = 3;
+
+// Everything needed to recreate a tournament in another deployment.
+// Rows keep the ids they had when exported so that they can refer to each other,
+// and are given new ids when imported.
+// Match output isn't included.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Archive {
+    pub version: i64,
+    pub export_time: i64,
+    // whether user ids and entry names have been replaced
+    pub anonymized: bool,
+    pub tournament: Tournament,
+    // every revision, oldest first
+    pub tournament_data: Vec<TournamentData>,
+    // the submissions entered into the tournament
+    pub submissions: Vec<Submission>,
+    // every entry, oldest first
+    pub tournament_submissions: Vec<TournamentSubmission>,
+    pub tournament_finalization: Option<TournamentFinalization>,
+    pub tournament_finalization_entries: Vec<TournamentFinalizationEntry>,
+    // practice play between the submissions, and the final evaluation if there is one
+    pub match_resolutions: Vec<MatchResolution>,
+}
+
+impl Archive {
+    // For publishing datasets.
+    // User ids are replaced by 1, 2, 3... in order of appearance,
+    // and entries are named after their submission id instead of whatever their author called them.
+    // Submission code is kept as is, so it may still identify its author.
+    pub fn anonymize(self) -> Archive {
+        let mut user_ids: HashMap<i64, i64> = HashMap::new();
+        let mut pseudonym = |user_id: i64| {
+            let next = user_ids.len() as i64 + 1;
+            *user_ids.entry(user_id).or_insert(next)
+        };
+        let entry_name = |submission_id: i64| format!("Submission {}", submission_id);
+
+        Archive {
+            anonymized: true,
+            tournament: Tournament {
+                creator_user_id: pseudonym(self.tournament.creator_user_id),
+                ..self.tournament
+            },
+            tournament_data: self
+                .tournament_data
+                .into_iter()
+                .map(|x| TournamentData {
+                    creator_user_id: pseudonym(x.creator_user_id),
+                    ..x
+                })
+                .collect(),
+            submissions: self
+                .submissions
+                .into_iter()
+                .map(|x| Submission {
+                    creator_user_id: pseudonym(x.creator_user_id),
+                    ..x
+                })
+                .collect(),
+            tournament_submissions: self
+                .tournament_submissions
+                .into_iter()
+                .map(|x| TournamentSubmission {
+                    creator_user_id: pseudonym(x.creator_user_id),
+                    name: entry_name(x.submission_id),
+                    ..x
+                })
+                .collect(),
+            tournament_finalization: self
+                .tournament_finalization
+                .map(|x| TournamentFinalization {
+                    creator_user_id: pseudonym(x.creator_user_id),
+                    ..x
+                }),
+            tournament_finalization_entries: self
+                .tournament_finalization_entries
+                .into_iter()
+                .map(|x| TournamentFinalizationEntry {
+                    name: entry_name(x.submission_id),
+                    ..x
+                })
+                .collect(),
+            ..self
+        }
+    }
+}
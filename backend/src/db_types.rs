@@ -1,6 +1,13 @@
-use super::request::TournamentSubmissionKind;
+use super::request::{Strategy, TournamentSubmissionKind};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
+// rows are serializable so that they can be archived, see archive.rs
+// their schemas are renamed so as not to clash with the types in response.rs
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[schemars(rename = "ArchivedSubmission")]
 pub struct Submission {
     pub submission_id: i64,
     pub creation_time: i64,
@@ -8,14 +15,18 @@ pub struct Submission {
     pub code: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[schemars(rename = "ArchivedTournament")]
 pub struct Tournament {
     pub tournament_id: i64,
     pub creation_time: i64,
     pub creator_user_id: i64,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[schemars(rename = "ArchivedTournamentData")]
 pub struct TournamentData {
     pub tournament_data_id: i64,
     pub creation_time: i64,
@@ -26,9 +37,14 @@ pub struct TournamentData {
     pub n_rounds: i64,
     pub n_matchups: i64,
     pub active: bool,
+    // modules that entries may not import
+    #[serde(default)]
+    pub disallowed_imports: Vec<String>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[schemars(rename = "ArchivedTournamentSubmission")]
 pub struct TournamentSubmission {
     pub tournament_submission_id: i64,
     pub creation_time: i64,
@@ -37,28 +53,76 @@ pub struct TournamentSubmission {
     pub submission_id: i64,
     pub name: String,
     pub kind: TournamentSubmissionKind,
+    pub auto_compete: bool,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[schemars(rename = "ArchivedTournamentFinalization")]
+pub struct TournamentFinalization {
+    pub tournament_finalization_id: i64,
+    pub creation_time: i64,
+    pub creator_user_id: i64,
+    pub tournament_id: i64,
+    pub n_rounds: i64,
+    pub n_matchups: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[schemars(rename = "ArchivedTournamentFinalizationEntry")]
+pub struct TournamentFinalizationEntry {
+    pub tournament_finalization_entry_id: i64,
+    pub creation_time: i64,
+    pub tournament_finalization_id: i64,
+    pub tournament_submission_id: i64,
+    pub submission_id: i64,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[schemars(rename = "ArchivedMatchResolution")]
 pub struct MatchResolution {
     pub match_resolution_id: i64,
     pub submission_id: i64,
     pub opponent_submission_id: i64,
     pub round: i64,
     pub matchup: i64,
+    pub tournament_finalization_id: Option<i64>,
     pub creation_time: i64,
     pub defected: Option<bool>,
+    // what python's random was seeded with, None for matches played before bots were seeded
+    #[serde(default)]
+    pub seed: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MatchResolutionOutput {
+    pub match_resolution_id: i64,
     pub stdout: String,
+    pub stdout_truncated: bool,
     pub stderr: String,
+    pub stderr_truncated: bool,
 }
 
+// both sides of a round, see match_resolution_service::stream_rounds
 #[derive(Clone, Debug)]
-pub struct MatchResolutionLite {
-    pub match_resolution_id: i64,
+pub struct MatchRound {
     pub submission_id: i64,
     pub opponent_submission_id: i64,
-    pub round: i64,
     pub matchup: i64,
-    pub creation_time: i64,
+    pub round: i64,
+    pub tournament_finalization_id: Option<i64>,
     pub defected: Option<bool>,
+    pub opponent_defected: Option<bool>,
+}
+
+// not archived: an imported tournament's reference entries become ordinary submissions
+#[derive(Clone, Debug)]
+pub struct ReferenceSubmission {
+    pub reference_submission_id: i64,
+    pub creation_time: i64,
+    pub submission_id: i64,
+    pub strategy: Strategy,
 }
@@ -1,15 +1,25 @@
 use crate::request::TournamentSubmissionKind;
 use crate::response::AppError;
 use crate::run_code::RunCodeService;
+use crate::InsertEvent;
+use crate::Limits;
 use crate::MatchupTask;
+use crate::QueuedMatches;
+use crate::{
+    DEFAULT_PAGE_LIMIT, MAX_PAGE_LIMIT, MAX_TEST_RUN_ROUNDS, OUTPUT_PRUNE_INTERVAL,
+    REFERENCE_CREATOR_USER_ID, STDERR_EXCERPT_LENGTH, STREAM_FENCE_MAX_SENT,
+    STREAM_HEARTBEAT_INTERVAL,
+};
 
 use super::Db;
 use auth_service_api::response::AuthError;
 use auth_service_api::response::User;
-use futures_util::{SinkExt, StreamExt};
+use futures_util::stream::SplitSink;
+use futures_util::{SinkExt, Stream, StreamExt};
 use tokio::sync::broadcast;
 use tokio::sync::mpsc;
 use tokio::sync::Mutex;
+use tokio_postgres::GenericClient;
 use warp::ws::Message;
 
 use super::request;
@@ -18,14 +28,29 @@ use super::response;
 use super::db_types::*;
 use super::utils;
 
+use super::activity_service;
+use super::analysis;
+use super::archive;
+use super::harness;
+use super::match_resolution_output_service;
 use super::match_resolution_service;
+use super::reference_submission_service;
+use super::standings;
+use super::strategies;
 use super::submission_service;
 use super::tournament_data_service;
+use super::tournament_finalization_entry_service;
+use super::tournament_finalization_service;
 use super::tournament_service;
 use super::tournament_submission_service;
 
+use std::collections::HashMap;
+use std::convert::Infallible;
 use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 
 use super::AppData;
 
@@ -65,6 +90,15 @@ pub fn report_base64_err(e: base64::DecodeError) -> response::AppError {
     response::AppError::InternalServerError
 }
 
+pub fn report_join_err(e: tokio::task::JoinError) -> response::AppError {
+    utils::log(utils::Event {
+        msg: e.to_string(),
+        source: e.source().map(|e| e.to_string()),
+        severity: utils::SeverityKind::Error,
+    });
+    response::AppError::InternalServerError
+}
+
 fn report_auth_err(e: AuthError) -> response::AppError {
     match e {
         AuthError::ApiKeyNonexistent => response::AppError::Unauthorized,
@@ -101,6 +135,25 @@ async fn fill_submission(
     })
 }
 
+async fn fill_reference_submission(
+    con: &mut tokio_postgres::Client,
+    reference_submission: ReferenceSubmission,
+) -> Result<response::ReferenceSubmission, response::AppError> {
+    let submission =
+        submission_service::get_by_submission_id(con, reference_submission.submission_id)
+            .await
+            .map_err(report_postgres_err)?
+            .ok_or(response::AppError::SubmissionNonexistent)?;
+
+    Ok(response::ReferenceSubmission {
+        reference_submission_id: reference_submission.reference_submission_id,
+        creation_time: reference_submission.creation_time,
+        submission: fill_submission(con, submission).await?,
+        name: strategies::name(&reference_submission.strategy).to_string(),
+        strategy: reference_submission.strategy,
+    })
+}
+
 async fn fill_tournament(
     _con: &mut tokio_postgres::Client,
     tournament: Tournament,
@@ -112,46 +165,150 @@ async fn fill_tournament(
     })
 }
 
+// loads every tournament referenced in one query, instead of one query per item
+async fn load_tournaments(
+    con: &mut tokio_postgres::Client,
+    tournament_ids: impl IntoIterator<Item = i64>,
+) -> Result<HashMap<i64, response::Tournament>, response::AppError> {
+    let mut tournament_ids: Vec<i64> = tournament_ids.into_iter().collect();
+    tournament_ids.sort();
+    tournament_ids.dedup();
+
+    let tournaments = tournament_service::get_by_tournament_ids(con, &tournament_ids)
+        .await
+        .map_err(report_postgres_err)?;
+
+    let mut resp_tournaments = HashMap::new();
+    for u in tournaments.into_iter() {
+        resp_tournaments.insert(u.tournament_id, fill_tournament(con, u).await?);
+    }
+    Ok(resp_tournaments)
+}
+
+fn get_loaded_tournament(
+    tournaments: &HashMap<i64, response::Tournament>,
+    tournament_id: i64,
+) -> Result<response::Tournament, response::AppError> {
+    tournaments
+        .get(&tournament_id)
+        .cloned()
+        .ok_or(response::AppError::TournamentNonexistent)
+}
+
 async fn fill_tournament_data(
     con: &mut tokio_postgres::Client,
     tournament_data: TournamentData,
 ) -> Result<response::TournamentData, response::AppError> {
-    let tournament = tournament_service::get_by_tournament_id(con, tournament_data.tournament_id)
-        .await
-        .map_err(report_postgres_err)?
-        .ok_or(response::AppError::TournamentNonexistent)?;
+    let tournaments = load_tournaments(con, [tournament_data.tournament_id]).await?;
+    fill_tournament_data_with(&tournaments, tournament_data)
+}
 
+// for filling many at once, with the tournaments preloaded by load_tournaments
+fn fill_tournament_data_with(
+    tournaments: &HashMap<i64, response::Tournament>,
+    tournament_data: TournamentData,
+) -> Result<response::TournamentData, response::AppError> {
     Ok(response::TournamentData {
         tournament_data_id: tournament_data.tournament_data_id,
         creation_time: tournament_data.creation_time,
         creator_user_id: tournament_data.creator_user_id,
-        tournament: fill_tournament(con, tournament).await?,
+        tournament: get_loaded_tournament(tournaments, tournament_data.tournament_id)?,
         title: tournament_data.title,
         description: tournament_data.description,
         n_matchups: tournament_data.n_matchups,
         n_rounds: tournament_data.n_rounds,
         active: tournament_data.active,
+        disallowed_imports: tournament_data.disallowed_imports,
     })
 }
 
+// the strategy of every reference submission among these, keyed by submission id
+async fn load_reference_strategies(
+    con: &mut tokio_postgres::Client,
+    submission_ids: impl IntoIterator<Item = i64>,
+) -> Result<HashMap<i64, request::Strategy>, response::AppError> {
+    let mut submission_ids: Vec<i64> = submission_ids.into_iter().collect();
+    submission_ids.sort();
+    submission_ids.dedup();
+
+    let reference_submissions =
+        reference_submission_service::get_by_submission_ids(con, &submission_ids)
+            .await
+            .map_err(report_postgres_err)?;
+
+    Ok(reference_submissions
+        .into_iter()
+        .map(|x| (x.submission_id, x.strategy))
+        .collect())
+}
+
 async fn fill_tournament_submission(
     con: &mut tokio_postgres::Client,
     tournament_submission: TournamentSubmission,
 ) -> Result<response::TournamentSubmission, response::AppError> {
-    let tournament =
-        tournament_service::get_by_tournament_id(con, tournament_submission.tournament_id)
-            .await
-            .map_err(report_postgres_err)?
-            .ok_or(response::AppError::TournamentNonexistent)?;
+    let tournaments = load_tournaments(con, [tournament_submission.tournament_id]).await?;
+    let reference_strategies =
+        load_reference_strategies(con, [tournament_submission.submission_id]).await?;
+    fill_tournament_submission_with(&tournaments, &reference_strategies, tournament_submission)
+}
 
+// for filling many at once, with the tournaments preloaded by load_tournaments
+// and the reference strategies by load_reference_strategies
+fn fill_tournament_submission_with(
+    tournaments: &HashMap<i64, response::Tournament>,
+    reference_strategies: &HashMap<i64, request::Strategy>,
+    tournament_submission: TournamentSubmission,
+) -> Result<response::TournamentSubmission, response::AppError> {
     Ok(response::TournamentSubmission {
         tournament_submission_id: tournament_submission.tournament_submission_id,
         creation_time: tournament_submission.creation_time,
         creator_user_id: tournament_submission.creator_user_id,
         submission_id: tournament_submission.submission_id,
-        tournament: fill_tournament(con, tournament).await?,
+        tournament: get_loaded_tournament(tournaments, tournament_submission.tournament_id)?,
         name: tournament_submission.name,
         kind: tournament_submission.kind,
+        auto_compete: tournament_submission.auto_compete,
+        reference_strategy: reference_strategies
+            .get(&tournament_submission.submission_id)
+            .cloned(),
+    })
+}
+
+async fn fill_tournament_finalization(
+    con: &mut tokio_postgres::Client,
+    tournament_finalization: TournamentFinalization,
+) -> Result<response::TournamentFinalization, response::AppError> {
+    let tournaments = load_tournaments(con, [tournament_finalization.tournament_id]).await?;
+    fill_tournament_finalization_with(&tournaments, tournament_finalization)
+}
+
+// for filling many at once, with the tournaments preloaded by load_tournaments
+fn fill_tournament_finalization_with(
+    tournaments: &HashMap<i64, response::Tournament>,
+    tournament_finalization: TournamentFinalization,
+) -> Result<response::TournamentFinalization, response::AppError> {
+    Ok(response::TournamentFinalization {
+        tournament_finalization_id: tournament_finalization.tournament_finalization_id,
+        creation_time: tournament_finalization.creation_time,
+        creator_user_id: tournament_finalization.creator_user_id,
+        tournament: get_loaded_tournament(tournaments, tournament_finalization.tournament_id)?,
+        n_rounds: tournament_finalization.n_rounds,
+        n_matchups: tournament_finalization.n_matchups,
+    })
+}
+
+async fn fill_tournament_finalization_entry(
+    _con: &mut tokio_postgres::Client,
+    tournament_finalization_entry: TournamentFinalizationEntry,
+) -> Result<response::TournamentFinalizationEntry, response::AppError> {
+    Ok(response::TournamentFinalizationEntry {
+        tournament_finalization_entry_id: tournament_finalization_entry
+            .tournament_finalization_entry_id,
+        creation_time: tournament_finalization_entry.creation_time,
+        tournament_finalization_id: tournament_finalization_entry.tournament_finalization_id,
+        tournament_submission_id: tournament_finalization_entry.tournament_submission_id,
+        submission_id: tournament_finalization_entry.submission_id,
+        name: tournament_finalization_entry.name,
     })
 }
 
@@ -166,9 +323,9 @@ async fn fill_match_resolution(
         opponent_submission_id: match_resolution.opponent_submission_id,
         round: match_resolution.round,
         matchup: match_resolution.matchup,
+        tournament_finalization_id: match_resolution.tournament_finalization_id,
         defected: match_resolution.defected,
-        stdout: match_resolution.stdout,
-        stderr: match_resolution.stderr,
+        seed: match_resolution.seed,
     })
 }
 
@@ -183,6 +340,7 @@ async fn fill_match_resolution_lite(
         opponent_submission_id: match_resolution.opponent_submission_id,
         round: match_resolution.round,
         matchup: match_resolution.matchup,
+        tournament_finalization_id: match_resolution.tournament_finalization_id,
         defected: match_resolution.defected,
     })
 }
@@ -197,22 +355,95 @@ pub async fn get_user_if_api_key_valid(
         .map_err(report_auth_err)
 }
 
+// clamps the requested page size and validates the cursor
+fn validate_page(limit: Option<i64>, cursor: &Option<String>) -> Result<i64, response::AppError> {
+    if let Some(cursor) = cursor {
+        utils::decode_cursor(cursor).ok_or(response::AppError::CursorInvalid)?;
+    }
+    Ok(limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT))
+}
+
+// only full pages have a next cursor
+fn make_page<T>(items: Vec<T>, limit: i64, last_id: Option<i64>) -> response::Page<T> {
+    response::Page {
+        next_cursor: if items.len() as i64 >= limit {
+            last_id.map(utils::encode_cursor)
+        } else {
+            None
+        },
+        items,
+    }
+}
+
+// parses the code in the sandbox without running it, see analysis.rs
+// None if there's no analysis: the sandbox couldn't be reached,
+// or the analysis didn't finish, such as when it timed out or the code was too deeply nested to parse
+// the checks in analysis.rs decide what to do with code that has none
+async fn analyze_code(run_code_service: &RunCodeService, code: &str) -> Option<analysis::Analysis> {
+    let summary = match run_code_service
+        .send_multifile_submission(analysis::analysis_files(code))
+        .await
+    {
+        Ok(summary) => summary,
+        Err(e) => {
+            utils::log(utils::Event {
+                msg: format!("could not analyze submission: {}", e),
+                source: None::<String>,
+                severity: utils::SeverityKind::Warning,
+            });
+            return None;
+        }
+    };
+
+    match serde_json::from_str(&summary.stdout) {
+        Ok(analysis) => Some(analysis),
+        Err(e) => {
+            utils::log(utils::Event {
+                msg: format!("could not analyze submission: {}", e),
+                source: Some(summary.stderr),
+                severity: utils::SeverityKind::Warning,
+            });
+            None
+        }
+    }
+}
+
 pub async fn submission_new(
     AppData {
-        db, auth_service, ..
+        db,
+        auth_service,
+        run_code_service,
+        limits,
+        ..
     }: AppData,
     props: request::SubmissionNewProps,
-) -> Result<response::Submission, response::AppError> {
+) -> Result<response::Submission, response::ErrorResponse> {
     // validate api key
     let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
     // validate code length
     if props.code.len() > 10000 {
-        return Err(response::AppError::SubmissionTooLong);
+        return Err(response::AppError::SubmissionTooLong.into());
     }
 
     let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
 
+    // validate that the user hasn't submitted too much in the last hour
+    let n_recent_submissions = submission_service::count_by_creator_user_id(
+        con,
+        user.user_id,
+        utils::current_time_millis() - 60 * 60 * 1000,
+    )
+    .await
+    .map_err(report_postgres_err)?;
+
+    if n_recent_submissions >= limits.max_submissions_per_hour {
+        return Err(response::AppError::SubmissionRateLimited.into());
+    }
+
+    // catch mistakes that would fail every testcase now, instead of after they've been run
+    analysis::check_callable(analyze_code(&run_code_service, &props.code).await.as_ref())?;
+
     let mut sp = con.transaction().await.map_err(report_postgres_err)?;
 
     // create submission
@@ -223,15 +454,168 @@ pub async fn submission_new(
     sp.commit().await.map_err(report_postgres_err)?;
 
     // return json
-    fill_submission(con, submission).await
+    Ok(fill_submission(con, submission).await?)
+}
+
+// Plays code against an opponent for a few rounds, the same way execute_match would.
+// Nothing is saved, so code can be checked before it's submitted.
+pub async fn submission_test_run(
+    AppData {
+        db,
+        auth_service,
+        run_code_service,
+        queued_matches,
+        limits,
+        ..
+    }: AppData,
+    props: request::SubmissionTestRunProps,
+) -> Result<response::SubmissionTestRun, response::AppError> {
+    // validate api key
+    let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+    // validate code length
+    if props.code.len() > 10000 {
+        return Err(response::AppError::SubmissionTooLong);
+    }
+
+    if props.n_rounds <= 0 || props.n_rounds > MAX_TEST_RUN_ROUNDS {
+        return Err(response::AppError::TestRunNRoundsInvalid);
+    }
+
+    // dry runs aren't part of a tournament: the code plays as submission 0, and the opponent as 1
+    let submission = Submission {
+        submission_id: 0,
+        creation_time: utils::current_time_millis(),
+        creator_user_id: user.user_id,
+        code: props.code,
+    };
+
+    let opponent_submission = match (props.opponent_submission_id, props.opponent_strategy) {
+        (Some(opponent_submission_id), None) => {
+            let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
+
+            let opponent_submission =
+                submission_service::get_by_submission_id(con, opponent_submission_id)
+                    .await
+                    .map_err(report_postgres_err)?
+                    .ok_or(AppError::SubmissionNonexistent)?;
+
+            // other people's submissions can only be played if they're a tournament's testcases,
+            // since a competitor's output would give away what its code does
+            if opponent_submission.creator_user_id != user.user_id
+                && !tournament_submission_service::is_recent_by_submission_kind(
+                    con,
+                    opponent_submission_id,
+                    &[TournamentSubmissionKind::Testcase],
+                )
+                .await
+                .map_err(report_postgres_err)?
+            {
+                return Err(AppError::SubmissionNonexistent);
+            }
+
+            Submission {
+                submission_id: 1,
+                ..opponent_submission
+            }
+        }
+        (None, Some(strategy)) => Submission {
+            submission_id: 1,
+            creation_time: 0,
+            creator_user_id: 0,
+            code: strategies::code(&strategy).to_owned(),
+        },
+        _ => return Err(AppError::TestRunOpponentInvalid),
+    };
+
+    // the rounds run right away rather than being queued, but they still take up the sandbox,
+    // so they're charged to the match budget until they're done, which also caps how many can run at once
+    // the reservation is refunded when it's dropped, even if the client goes away mid run
+    let _reservation = reserve_matches(&queued_matches, user.user_id, props.n_rounds * 2, &limits)?;
+
+    let rounds = play_test_run(
+        &run_code_service,
+        &limits,
+        &submission,
+        &opponent_submission,
+        props.n_rounds,
+    )
+    .await?;
+
+    Ok(response::SubmissionTestRun { rounds })
+}
+
+async fn play_test_run(
+    run_code_service: &RunCodeService,
+    limits: &Limits,
+    submission: &Submission,
+    opponent_submission: &Submission,
+    n_rounds: i64,
+) -> Result<Vec<response::TestRunRound>, AppError> {
+    let mut defection_history = vec![];
+    let mut opponent_defection_history = vec![];
+    let mut rounds = vec![];
+
+    for round in 0..n_rounds {
+        let files = harness::match_files(
+            submission,
+            opponent_submission,
+            &opponent_defection_history,
+            Some(harness::match_seed(0, round, 0, 1)),
+        );
+
+        let opponent_files = harness::match_files(
+            opponent_submission,
+            submission,
+            &defection_history,
+            Some(harness::match_seed(0, round, 1, 0)),
+        );
+
+        // run both sides concurrently
+        let (resp, opponent_resp) = tokio::join!(
+            run_code_service.send_multifile_submission(files),
+            run_code_service.send_multifile_submission(opponent_files)
+        );
+        let resp = resp?;
+        let opponent_resp = opponent_resp?;
+
+        let defected = harness::defected_from_exit_code(resp.exit_code);
+        let opponent_defected = harness::defected_from_exit_code(opponent_resp.exit_code);
+
+        let payoff = |defected: Option<bool>, opponent_defected: Option<bool>| {
+            Some(standings::score_prisoners_dilemma(
+                defected?,
+                opponent_defected?,
+            ))
+        };
+
+        rounds.push(response::TestRunRound {
+            round,
+            defected,
+            opponent_defected,
+            payoff: payoff(defected, opponent_defected),
+            opponent_payoff: payoff(opponent_defected, defected),
+            stdout: utils::head(&resp.stdout, limits.max_output_length),
+            stdout_truncated: resp.stdout.len() > limits.max_output_length,
+            stderr: utils::tail(&resp.stderr, limits.max_output_length),
+            stderr_truncated: resp.stderr.len() > limits.max_output_length,
+        });
+
+        defection_history.push(defected);
+        opponent_defection_history.push(opponent_defected);
+    }
+
+    Ok(rounds)
 }
 
 pub async fn matchup_runner(
     db: Db,
     run_code_service: RunCodeService,
+    matchup_task_tx: mpsc::UnboundedSender<MatchupTask>,
     matchup_task_rx: Arc<Mutex<mpsc::UnboundedReceiver<MatchupTask>>>,
-    match_resolution_insert_tx: broadcast::Sender<response::MatchResolutionLite>,
     ongoing_tasks: Arc<Mutex<Vec<MatchupTask>>>,
+    queued_matches: QueuedMatches,
+    limits: Limits,
 ) {
     loop {
         let task = matchup_task_rx.lock().await.recv().await.unwrap();
@@ -245,8 +629,10 @@ pub async fn matchup_runner(
                 x.matchup_num == task.matchup_num
                     && x.submission_id == task.submission_id
                     && x.opponent_submission_id == task.opponent_submission_id
+                    && x.tournament_finalization_id == task.tournament_finalization_id
                     && x.n_rounds >= task.n_rounds
             }) {
+                release_match_budget(&queued_matches, &task);
                 continue;
             }
 
@@ -259,15 +645,202 @@ pub async fn matchup_runner(
             db.clone(),
             task.clone(),
             run_code_service.clone(),
-            match_resolution_insert_tx.clone(),
+            limits.max_output_length,
         )
         .await;
 
         // remove from ongoing task
         ongoing_tasks.lock().await.retain(|x| x != &task);
 
+        // give the matches back to the user's budget
+        release_match_budget(&queued_matches, &task);
+
         // unwrap result
         result.unwrap();
+
+        // finalization matches don't count towards validation
+        // database errors have already been logged where they occurred
+        if task.tournament_finalization_id.is_none() {
+            let _ = auto_compete(&db, &matchup_task_tx, &queued_matches, &limits, &task).await;
+        }
+    }
+}
+
+// Inserts from every instance are announced with NOTIFY by a trigger (see migrations/0003_notify_insert.sql).
+// This listens for them and feeds them into our local broadcast channels.
+pub async fn insert_listener(
+    postgres_config: tokio_postgres::Config,
+    db: Db,
+    match_resolution_insert_tx: broadcast::Sender<InsertEvent<response::MatchResolutionLite>>,
+    tournament_data_insert_tx: broadcast::Sender<InsertEvent<response::TournamentData>>,
+    tournament_submission_insert_tx: broadcast::Sender<InsertEvent<response::TournamentSubmission>>,
+) {
+    loop {
+        let _ = listen_for_inserts(
+            &postgres_config,
+            &db,
+            &match_resolution_insert_tx,
+            &tournament_data_insert_tx,
+            &tournament_submission_insert_tx,
+        )
+        .await;
+
+        utils::log(utils::Event {
+            msg: "insert listener disconnected, reconnecting".to_owned(),
+            source: None::<String>,
+            severity: utils::SeverityKind::Warning,
+        });
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+async fn listen_for_inserts(
+    postgres_config: &tokio_postgres::Config,
+    db: &Db,
+    match_resolution_insert_tx: &broadcast::Sender<InsertEvent<response::MatchResolutionLite>>,
+    tournament_data_insert_tx: &broadcast::Sender<InsertEvent<response::TournamentData>>,
+    tournament_submission_insert_tx: &broadcast::Sender<
+        InsertEvent<response::TournamentSubmission>,
+    >,
+) -> Result<(), AppError> {
+    // LISTEN needs its own connection rather than one from the pool
+    let (client, mut connection) = postgres_config
+        .connect(tokio_postgres::NoTls)
+        .await
+        .map_err(report_postgres_err)?;
+
+    // the connection has to be polled for notifications to arrive
+    let (notification_tx, mut notification_rx) = mpsc::unbounded_channel();
+    tokio::task::spawn(async move {
+        let mut messages = futures_util::stream::poll_fn(move |cx| connection.poll_message(cx));
+        while let Some(message) = messages.next().await {
+            match message {
+                Ok(tokio_postgres::AsyncMessage::Notification(notification)) => {
+                    if notification_tx.send(notification).is_err() {
+                        return;
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    report_postgres_err(e);
+                    return;
+                }
+            }
+        }
+    });
+
+    client
+        .batch_execute(
+            "LISTEN match_resolution_insert;
+             LISTEN tournament_data_insert;
+             LISTEN tournament_submission_insert;",
+        )
+        .await
+        .map_err(report_postgres_err)?;
+
+    // inserts made while we weren't listening were never announced to us
+    let _ = match_resolution_insert_tx.send(InsertEvent::Resync);
+    let _ = tournament_data_insert_tx.send(InsertEvent::Resync);
+    let _ = tournament_submission_insert_tx.send(InsertEvent::Resync);
+
+    while let Some(notification) = notification_rx.recv().await {
+        // the payload is the id of the inserted row
+        let id: i64 = match notification.payload().parse() {
+            Ok(id) => id,
+            Err(_) => continue,
+        };
+
+        let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
+
+        // broadcast to all queues, ignoring the result
+        match notification.channel() {
+            "match_resolution_insert" => {
+                if let Some(match_resolution) =
+                    match_resolution_service::get_by_match_resolution_id(con, id)
+                        .await
+                        .map_err(report_postgres_err)?
+                {
+                    let _ = match_resolution_insert_tx.send(InsertEvent::Insert(
+                        fill_match_resolution_lite(con, match_resolution).await?,
+                    ));
+                }
+            }
+            "tournament_data_insert" => {
+                if let Some(tournament_data) =
+                    tournament_data_service::get_by_tournament_data_id(con, id)
+                        .await
+                        .map_err(report_postgres_err)?
+                {
+                    let _ = tournament_data_insert_tx.send(InsertEvent::Insert(
+                        fill_tournament_data(con, tournament_data).await?,
+                    ));
+                }
+            }
+            "tournament_submission_insert" => {
+                if let Some(tournament_submission) =
+                    tournament_submission_service::get_by_tournament_submission_id(con, id)
+                        .await
+                        .map_err(report_postgres_err)?
+                {
+                    let _ = tournament_submission_insert_tx.send(InsertEvent::Insert(
+                        fill_tournament_submission(con, tournament_submission).await?,
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // the connection closed
+    Ok(())
+}
+
+// Periodically deletes match output that is no longer useful, see match_resolution_output_service::prune.
+// Every instance runs this, which is fine since pruning twice does nothing.
+pub async fn output_pruner(db: Db, output_retention: Duration) {
+    loop {
+        tokio::time::sleep(OUTPUT_PRUNE_INTERVAL).await;
+
+        let min_creation_time = utils::current_time_millis() - output_retention.as_millis() as i64;
+
+        // errors have already been logged, so just try again next time
+        if let Ok(n_pruned) = prune_outputs(&db, min_creation_time).await {
+            utils::log(utils::Event {
+                msg: format!("pruned {} match outputs", n_pruned),
+                source: None::<String>,
+                severity: utils::SeverityKind::Info,
+            });
+        }
+    }
+}
+
+async fn prune_outputs(db: &Db, min_creation_time: i64) -> Result<u64, AppError> {
+    let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
+    match_resolution_output_service::prune(con, min_creation_time)
+        .await
+        .map_err(report_postgres_err)
+}
+
+fn release_match_budget(queued_matches: &QueuedMatches, task: &MatchupTask) {
+    if let Some(user_id) = task.budget_user_id {
+        refund_match_budget(queued_matches, user_id, task.n_matches());
+    }
+}
+
+// whether any of the matches charged to the user's budget are still queued or running
+fn has_queued_matches(queued_matches: &QueuedMatches, user_id: i64) -> bool {
+    queued_matches.lock().unwrap().contains_key(&user_id)
+}
+
+// gives matches back to the user's budget, once they've been played or if they never get queued
+fn refund_match_budget(queued_matches: &QueuedMatches, user_id: i64, n_matches: i64) {
+    let mut queued_matches = queued_matches.lock().unwrap();
+    if let Some(n_queued_matches) = queued_matches.get_mut(&user_id) {
+        *n_queued_matches -= n_matches;
+        if *n_queued_matches <= 0 {
+            queued_matches.remove(&user_id);
+        }
     }
 }
 
@@ -278,9 +851,11 @@ async fn run_matchup(
         n_rounds,
         submission_id,
         opponent_submission_id,
+        tournament_finalization_id,
+        ..
     }: MatchupTask,
     run_code_service: RunCodeService,
-    match_resolution_insert_tx: broadcast::Sender<response::MatchResolutionLite>,
+    max_output_length: usize,
 ) -> Result<(), AppError> {
     // query the rounds that already exist for this matchup
     let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
@@ -301,6 +876,7 @@ async fn run_matchup(
             submission.submission_id,
             opponent_submission.submission_id,
             matchup_num,
+            tournament_finalization_id,
         )
         .await
         .map_err(report_postgres_err)?
@@ -314,6 +890,7 @@ async fn run_matchup(
             opponent_submission.submission_id,
             submission.submission_id,
             matchup_num,
+            tournament_finalization_id,
         )
         .await
         .map_err(report_postgres_err)?
@@ -337,9 +914,10 @@ async fn run_matchup(
             &opponent_submission,
             round,
             matchup_num,
+            tournament_finalization_id,
             &opponent_defection_history,
             &run_code_service,
-            match_resolution_insert_tx.clone(),
+            max_output_length,
         );
 
         let opponent_submission_match_resolution_pr = execute_match(
@@ -348,9 +926,10 @@ async fn run_matchup(
             &submission,
             round,
             matchup_num,
+            tournament_finalization_id,
             &submission_defection_history,
             &run_code_service,
-            match_resolution_insert_tx.clone(),
+            max_output_length,
         );
 
         // await both futures concurrently
@@ -363,108 +942,159 @@ async fn run_matchup(
         opponent_defection_history.push(opponent_submission_match_resolution?.defected);
     }
 
-    return Ok(());
+    Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn execute_match(
     db: Db,
     submission: &Submission,
     opponent_submission: &Submission,
     round: i64,
     matchup: i64,
-    opponent_defection_history: &Vec<Option<bool>>,
+    tournament_finalization_id: Option<i64>,
+    opponent_defection_history: &[Option<bool>],
     run_code_service: &RunCodeService,
-    match_resolution_insert_tx: broadcast::Sender<response::MatchResolutionLite>,
+    max_output_length: usize,
 ) -> Result<MatchResolution, AppError> {
-    let opponent_defection_history_str = opponent_defection_history
-        .into_iter()
-        .map(|x| match x {
-            None => "None",
-            Some(true) => "True",
-            Some(false) => "False",
-        })
-        .collect::<Vec<&str>>()
-        .join(",");
-
-    // create random names for submission and opponent submission
-    let submission_file_name = format!("mod_{}", utils::random_string());
-    let opponent_submission_file_name = format!("mod_{}", utils::random_string());
-
-    let run_code = [
-        String::from("#!/usr/bin/env python3"),
-        format!("import {} as Sub", submission_file_name),
-        format!("import {} as Opp", opponent_submission_file_name),
-        format!(
-            "opp_defection_history = [{}]",
-            opponent_defection_history_str
-        ),
-        String::from("defected = Sub.should_defect(Opp.should_defect, opp_defection_history)"),
-        format!("exit(100 if defected else 101)"),
-    ]
-    .join("\n");
-
-    let map = [
-        (String::from("run"), run_code),
-        (
-            format!("{}.py", submission_file_name),
-            submission.code.clone(),
-        ),
-        (
-            format!("{}.py", opponent_submission_file_name),
-            opponent_submission.code.clone(),
-        ),
-    ]
-    .into_iter()
-    .collect();
+    let seed = harness::match_seed(
+        matchup,
+        round,
+        submission.submission_id,
+        opponent_submission.submission_id,
+    );
+
+    let map = harness::match_files(
+        submission,
+        opponent_submission,
+        opponent_defection_history,
+        Some(seed),
+    );
 
     let resp = run_code_service.send_multifile_submission(map).await?;
 
-    let defected = match resp.exit_code {
-        Some(100) => Some(true),
-        Some(101) => Some(false),
-        _ => None,
-    };
+    let defected = harness::defected_from_exit_code(resp.exit_code);
 
     // create a match resolution in the case that the run_code callback fails
     let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
 
+    let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
     let match_resolution = match_resolution_service::add(
-        con,
+        &mut sp,
         submission.submission_id,
         opponent_submission.submission_id,
         round,
         matchup,
+        tournament_finalization_id,
         defected,
-        resp.stdout,
-        resp.stderr,
+        Some(seed),
+    )
+    .await
+    .map_err(report_postgres_err)?;
+
+    // keep the start of stdout, and the end of stderr where the traceback is
+    match_resolution_output_service::add(
+        &mut sp,
+        match_resolution.match_resolution_id,
+        utils::head(&resp.stdout, max_output_length),
+        resp.stdout.len() > max_output_length,
+        utils::tail(&resp.stderr, max_output_length),
+        resp.stderr.len() > max_output_length,
     )
     .await
     .map_err(report_postgres_err)?;
 
-    // broadcast to all queues, ignoring the result
-    let _ = match_resolution_insert_tx
-        .send(fill_match_resolution_lite(con, match_resolution.clone()).await?);
+    sp.commit().await.map_err(report_postgres_err)?;
 
     Ok(match_resolution)
 }
 
-pub async fn tournament_new(
-    AppData {
-        db, auth_service, ..
-    }: AppData,
-    props: request::TournamentNewProps,
-) -> Result<response::TournamentData, response::AppError> {
-    // validate api key
-    let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+// Rebuilds the files that execute_match ran for a match resolution, so a bot can be debugged locally.
+// Module names are random, so they'll differ from the original run, but nothing else does.
+pub async fn match_resolution_files(
+    con: &mut impl GenericClient,
+    match_resolution_id: i64,
+) -> Result<(MatchResolution, HashMap<String, String>), AppError> {
+    let match_resolution =
+        match_resolution_service::get_by_match_resolution_id(con, match_resolution_id)
+            .await
+            .map_err(report_postgres_err)?
+            .ok_or(AppError::MatchResolutionNonexistent)?;
 
-    if props.n_rounds <= 0 {
-        return Err(AppError::TournamentDataNRoundsInvalid);
-    }
-    if props.n_matchups <= 0 {
-        return Err(AppError::TournamentDataNMatchupsInvalid);
-    }
+    let submission = submission_service::get_by_submission_id(con, match_resolution.submission_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(AppError::SubmissionNonexistent)?;
 
-    let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
+    let opponent_submission =
+        submission_service::get_by_submission_id(con, match_resolution.opponent_submission_id)
+            .await
+            .map_err(report_postgres_err)?
+            .ok_or(AppError::SubmissionNonexistent)?;
+
+    let opponent_defection_history: Vec<Option<bool>> =
+        match_resolution_service::get_defection_history_before(
+            con,
+            match_resolution.opponent_submission_id,
+            match_resolution.submission_id,
+            match_resolution.matchup,
+            match_resolution.tournament_finalization_id,
+            match_resolution.round,
+            match_resolution.match_resolution_id,
+        )
+        .await
+        .map_err(report_postgres_err)?
+        .into_iter()
+        .map(|x| x.defected)
+        .collect();
+
+    let files = harness::match_files(
+        &submission,
+        &opponent_submission,
+        &opponent_defection_history,
+        match_resolution.seed,
+    );
+
+    Ok((match_resolution, files))
+}
+
+// trims and dedups a tournament's disallowed imports, and checks that they're module names
+fn normalize_disallowed_imports(
+    disallowed_imports: Vec<String>,
+) -> Result<Vec<String>, response::AppError> {
+    let mut normalized: Vec<String> = vec![];
+    for module in disallowed_imports {
+        let module = module.trim();
+        if !analysis::is_module_name(module) {
+            return Err(AppError::TournamentDataDisallowedImportInvalid);
+        }
+        if !normalized.iter().any(|x| x == module) {
+            normalized.push(module.to_string());
+        }
+    }
+    Ok(normalized)
+}
+
+pub async fn tournament_new(
+    AppData {
+        db, auth_service, ..
+    }: AppData,
+    props: request::TournamentNewProps,
+) -> Result<response::TournamentData, response::AppError> {
+    // validate api key
+    let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+    if props.n_rounds <= 0 {
+        return Err(AppError::TournamentDataNRoundsInvalid);
+    }
+    if props.n_matchups <= 0 {
+        return Err(AppError::TournamentDataNMatchupsInvalid);
+    }
+
+    let disallowed_imports = normalize_disallowed_imports(props.disallowed_imports)?;
+
+    let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
 
     let mut sp = con.transaction().await.map_err(report_postgres_err)?;
 
@@ -483,6 +1113,7 @@ pub async fn tournament_new(
         props.n_rounds,
         props.n_matchups,
         true,
+        disallowed_imports,
     )
     .await
     .map_err(report_postgres_err)?;
@@ -516,6 +1147,11 @@ pub async fn tournament_data_new(
         return Err(AppError::TournamentDataTooManyMatches);
     }
 
+    let disallowed_imports = props
+        .disallowed_imports
+        .map(normalize_disallowed_imports)
+        .transpose()?;
+
     let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
 
     let mut sp = con.transaction().await.map_err(report_postgres_err)?;
@@ -583,10 +1219,13 @@ pub async fn tournament_data_new(
                 for testcase_id in &testcase_ids {
                     matchup_task_tx
                         .send(MatchupTask {
+                            tournament_id: tournament.tournament_id,
                             matchup_num: i,
                             n_rounds: props.n_rounds,
                             submission_id: *totest_id,
                             opponent_submission_id: *testcase_id,
+                            tournament_finalization_id: None,
+                            budget_user_id: None,
                         })
                         .unwrap();
                 }
@@ -596,10 +1235,13 @@ pub async fn tournament_data_new(
                 for b in 0..=usize::min(a, compete_ids.len()) {
                     matchup_task_tx
                         .send(MatchupTask {
+                            tournament_id: tournament.tournament_id,
                             matchup_num: i,
                             n_rounds: props.n_rounds,
                             submission_id: compete_ids[a],
                             opponent_submission_id: compete_ids[b],
+                            tournament_finalization_id: None,
+                            budget_user_id: None,
                         })
                         .unwrap();
                 }
@@ -617,6 +1259,8 @@ pub async fn tournament_data_new(
         props.n_rounds,
         props.n_matchups,
         props.active,
+        // entries that have already been made are kept if the policy changes
+        disallowed_imports.unwrap_or(old_td.disallowed_imports),
     )
     .await
     .map_err(report_postgres_err)?;
@@ -627,50 +1271,550 @@ pub async fn tournament_data_new(
     fill_tournament_data(con, tournament_data).await
 }
 
+// the recent match resolutions between the submission and each of the tournament's testcases
+// in both directions, since there can't be any errors no matter which program is judging
+async fn get_testcase_results(
+    con: &mut impl GenericClient,
+    tournament_id: i64,
+    submission_id: i64,
+) -> Result<Vec<(TournamentSubmission, Vec<MatchResolution>)>, response::AppError> {
+    let mut results = vec![];
+    for testcase in tournament_submission_service::get_recent_by_kind(
+        con,
+        tournament_id,
+        &[request::TournamentSubmissionKind::Testcase],
+    )
+    .await
+    .map_err(report_postgres_err)?
+    {
+        let mut testcase_results = vec![];
+        // the submission judging the testcase
+        testcase_results.append(
+            &mut match_resolution_service::get_recent_by_submission(
+                con,
+                submission_id,
+                testcase.submission_id,
+            )
+            .await
+            .map_err(report_postgres_err)?,
+        );
+        // the testcase judging the submission
+        testcase_results.append(
+            &mut match_resolution_service::get_recent_by_submission(
+                con,
+                testcase.submission_id,
+                submission_id,
+            )
+            .await
+            .map_err(report_postgres_err)?,
+        );
+        results.push((testcase, testcase_results));
+    }
+    Ok(results)
+}
+
+// checks that a validated submission may start competing,
+// and returns its round robin against the other competing entries
+async fn compete_tasks(
+    con: &mut impl GenericClient,
+    tournament_data: &TournamentData,
+    submission_id: i64,
+    user_id: i64,
+    limits: &Limits,
+) -> Result<Vec<MatchupTask>, response::ErrorResponse> {
+    // ensure the user hasn't exceeded their compete entries in this tournament
+    let n_compete = tournament_submission_service::count_recent_by_creator_kind(
+        con,
+        tournament_data.tournament_id,
+        user_id,
+        TournamentSubmissionKind::Compete,
+    )
+    .await
+    .map_err(report_postgres_err)?;
+
+    if n_compete >= limits.max_compete_per_tournament {
+        return Err(AppError::TournamentSubmissionCompeteLimit.into());
+    }
+
+    // ensure that testcases have all passed
+    for (testcase, testcase_results) in
+        get_testcase_results(con, tournament_data.tournament_id, submission_id).await?
+    {
+        // ensure that there are at least tournament_data
+        if testcase_results.len()
+            < (tournament_data.n_rounds * tournament_data.n_matchups * 2) as usize
+        {
+            return Err(response::ErrorResponse::with_details(
+                AppError::TournamentSubmissionTestcaseIncomplete,
+                response::ErrorDetails::Testcase {
+                    testcase_tournament_submission_id: testcase.tournament_submission_id,
+                    testcase_submission_id: testcase.submission_id,
+                    match_resolution_id: None,
+                },
+            ));
+        }
+
+        for result in testcase_results {
+            if result.defected.is_none() {
+                return Err(response::ErrorResponse::with_details(
+                    AppError::TournamentSubmissionTestcaseFails,
+                    response::ErrorDetails::Testcase {
+                        testcase_tournament_submission_id: testcase.tournament_submission_id,
+                        testcase_submission_id: testcase.submission_id,
+                        match_resolution_id: Some(result.match_resolution_id),
+                    },
+                ));
+            }
+        }
+    }
+
+    compete_matchups(con, tournament_data, submission_id, Some(user_id)).await
+}
+
+// the round robin of a submission that starts competing, against the other competing entries
+async fn compete_matchups(
+    con: &mut impl GenericClient,
+    tournament_data: &TournamentData,
+    submission_id: i64,
+    budget_user_id: Option<i64>,
+) -> Result<Vec<MatchupTask>, response::ErrorResponse> {
+    let mut tasks = vec![];
+
+    // then do match for all other submission entries
+    for opponent in tournament_submission_service::get_recent_by_kind(
+        con,
+        tournament_data.tournament_id,
+        &[request::TournamentSubmissionKind::Compete],
+    )
+    .await
+    .map_err(report_postgres_err)?
+    {
+        for i in 0..tournament_data.n_matchups {
+            tasks.push(MatchupTask {
+                tournament_id: tournament_data.tournament_id,
+                matchup_num: i,
+                n_rounds: tournament_data.n_rounds,
+                submission_id,
+                opponent_submission_id: opponent.submission_id,
+                tournament_finalization_id: None,
+                budget_user_id,
+            });
+        }
+    }
+
+    // also matchup against self
+    for i in 0..tournament_data.n_matchups {
+        tasks.push(MatchupTask {
+            tournament_id: tournament_data.tournament_id,
+            matchup_num: i,
+            n_rounds: tournament_data.n_rounds,
+            submission_id,
+            opponent_submission_id: submission_id,
+            tournament_finalization_id: None,
+            budget_user_id,
+        });
+    }
+
+    Ok(tasks)
+}
+
+// charges the user's match budget for these, if they have enough left
+// the reservation is refunded if it's dropped before the tasks are queued, see MatchReservation::hand_over
+// only tasks counted against the user's budget are charged, since only those are released when they finish
+fn reserve_match_budget(
+    queued_matches: &QueuedMatches,
+    user_id: i64,
+    tasks: &[MatchupTask],
+    limits: &Limits,
+) -> Result<MatchReservation, AppError> {
+    let n_new_matches: i64 = tasks
+        .iter()
+        .filter(|x| x.budget_user_id == Some(user_id))
+        .map(MatchupTask::n_matches)
+        .sum();
+    reserve_matches(queued_matches, user_id, n_new_matches, limits)
+}
+
+// matches charged to a user's budget, which are given back when this is dropped
+struct MatchReservation {
+    queued_matches: QueuedMatches,
+    user_id: i64,
+    n_matches: i64,
+}
+
+impl MatchReservation {
+    // the matches have been queued, and the workers release them as they finish instead
+    fn hand_over(mut self) {
+        self.n_matches = 0;
+    }
+}
+
+impl Drop for MatchReservation {
+    fn drop(&mut self) {
+        refund_match_budget(&self.queued_matches, self.user_id, self.n_matches);
+    }
+}
+
+// charges the user's match budget, including for matches that aren't queued as tasks, such as a test run's
+// the check and the charge happen under one lock, so that concurrent requests can't all fit in the same budget
+fn reserve_matches(
+    queued_matches: &QueuedMatches,
+    user_id: i64,
+    n_new_matches: i64,
+    limits: &Limits,
+) -> Result<MatchReservation, AppError> {
+    if n_new_matches > 0 {
+        let mut queued_matches = queued_matches.lock().unwrap();
+        let n_queued_matches = queued_matches.entry(user_id).or_insert(0);
+        if *n_queued_matches + n_new_matches > limits.max_queued_matches_per_user {
+            if *n_queued_matches == 0 {
+                queued_matches.remove(&user_id);
+            }
+            return Err(AppError::MatchBudgetExceeded);
+        }
+        *n_queued_matches += n_new_matches;
+    }
+    Ok(MatchReservation {
+        queued_matches: queued_matches.clone(),
+        user_id,
+        n_matches: n_new_matches,
+    })
+}
+
+// enters an auto compete validation entry into the tournament if all its testcases have passed
+// returns whether it was promoted
+async fn try_auto_compete(
+    con: &mut tokio_postgres::Client,
+    matchup_task_tx: &mpsc::UnboundedSender<MatchupTask>,
+    queued_matches: &QueuedMatches,
+    limits: &Limits,
+    candidate: TournamentSubmission,
+) -> Result<bool, response::ErrorResponse> {
+    let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+    // another worker may be finishing a testcase round for the same entry
+    tournament_submission_service::lock_tournament_submission(
+        &mut sp,
+        candidate.tournament_id,
+        candidate.submission_id,
+    )
+    .await
+    .map_err(report_postgres_err)?;
+
+    // ensure it's still waiting now that we hold the lock
+    match tournament_submission_service::get_recent_by_tournament_submission(
+        &mut sp,
+        candidate.tournament_id,
+        candidate.submission_id,
+    )
+    .await
+    .map_err(report_postgres_err)?
+    {
+        Some(x) if x.tournament_submission_id == candidate.tournament_submission_id => {}
+        _ => return Ok(false),
+    }
+
+    let tournament_data =
+        tournament_data_service::get_recent_by_tournament_id(&mut sp, candidate.tournament_id)
+            .await
+            .map_err(report_postgres_err)?
+            .ok_or(response::AppError::TournamentNonexistent)?;
+
+    if !tournament_data.active {
+        return Err(AppError::TournamentArchived.into());
+    }
+
+    if tournament_finalization_service::get_by_tournament_id(&mut sp, candidate.tournament_id)
+        .await
+        .map_err(report_postgres_err)?
+        .is_some()
+    {
+        return Err(AppError::TournamentFinalized.into());
+    }
+
+    // the same conditions as a manual request to compete
+    let tasks = compete_tasks(
+        &mut sp,
+        &tournament_data,
+        candidate.submission_id,
+        candidate.creator_user_id,
+        limits,
+    )
+    .await?;
+
+    let reservation =
+        reserve_match_budget(queued_matches, candidate.creator_user_id, &tasks, limits)?;
+
+    tournament_submission_service::add(
+        &mut sp,
+        candidate.creator_user_id,
+        candidate.submission_id,
+        candidate.tournament_id,
+        candidate.name,
+        TournamentSubmissionKind::Compete,
+        false,
+    )
+    .await
+    .map_err(report_postgres_err)?;
+
+    sp.commit().await.map_err(report_postgres_err)?;
+
+    // queue the matchups
+    reservation.hand_over();
+    for task in tasks {
+        matchup_task_tx.send(task).unwrap();
+    }
+
+    Ok(true)
+}
+
+// called after a practice matchup finishes, since it may have been the last testcase round
+// that an auto compete entry of one of its submissions was waiting on
+async fn auto_compete(
+    db: &Db,
+    matchup_task_tx: &mpsc::UnboundedSender<MatchupTask>,
+    queued_matches: &QueuedMatches,
+    limits: &Limits,
+    task: &MatchupTask,
+) -> Result<(), AppError> {
+    let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
+
+    // only entries with the other side as a testcase were waiting on this matchup
+    let mut candidates = tournament_submission_service::get_recent_auto_compete_by_testcase_pair(
+        con,
+        task.submission_id,
+        task.opponent_submission_id,
+    )
+    .await
+    .map_err(report_postgres_err)?;
+
+    // entries refused for the match budget are tried again once all of the user's queued matches have finished,
+    // since that's when they have the most budget to spare
+    if let Some(user_id) = task
+        .budget_user_id
+        .filter(|x| !has_queued_matches(queued_matches, *x))
+    {
+        for candidate in
+            tournament_submission_service::get_recent_auto_compete_by_creator_user_id(con, user_id)
+                .await
+                .map_err(report_postgres_err)?
+        {
+            if !candidates
+                .iter()
+                .any(|x| x.tournament_submission_id == candidate.tournament_submission_id)
+            {
+                candidates.push(candidate);
+            }
+        }
+    }
+
+    promote_auto_compete(con, matchup_task_tx, queued_matches, limits, candidates).await;
+
+    Ok(())
+}
+
+// enters each of the auto compete entries whose testcases have all passed
+async fn promote_auto_compete(
+    con: &mut tokio_postgres::Client,
+    matchup_task_tx: &mpsc::UnboundedSender<MatchupTask>,
+    queued_matches: &QueuedMatches,
+    limits: &Limits,
+    candidates: Vec<TournamentSubmission>,
+) {
+    for candidate in candidates {
+        match try_auto_compete(
+            con,
+            matchup_task_tx,
+            queued_matches,
+            limits,
+            candidate.clone(),
+        )
+        .await
+        {
+            Ok(_) => {}
+            // the remaining testcase rounds will try again
+            Err(response::ErrorResponse {
+                code: AppError::TournamentSubmissionTestcaseIncomplete,
+                ..
+            }) => {}
+            // tried again whenever the user's queued matches finish, and so give back budget
+            Err(response::ErrorResponse {
+                code: AppError::MatchBudgetExceeded,
+                ..
+            }) => {}
+            // the entry stays in validation, and the user can still ask to compete themselves
+            // nothing that finishes later would change this, so it isn't tried again
+            Err(e) => {
+                if let Ok(true) = stop_auto_compete(con, &candidate).await {
+                    utils::log(utils::Event {
+                        msg: format!(
+                            "could not auto compete tournament submission {}: {}",
+                            candidate.tournament_submission_id, e.message
+                        ),
+                        source: None::<String>,
+                        severity: utils::SeverityKind::Info,
+                    });
+                }
+            }
+        }
+    }
+}
+
+// replaces an auto compete entry with one that doesn't, unless it has changed since
+// returns whether it was replaced, so that only one of the workers that see it fail does so
+async fn stop_auto_compete(
+    con: &mut tokio_postgres::Client,
+    candidate: &TournamentSubmission,
+) -> Result<bool, AppError> {
+    let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+    tournament_submission_service::lock_tournament_submission(
+        &mut sp,
+        candidate.tournament_id,
+        candidate.submission_id,
+    )
+    .await
+    .map_err(report_postgres_err)?;
+
+    match tournament_submission_service::get_recent_by_tournament_submission(
+        &mut sp,
+        candidate.tournament_id,
+        candidate.submission_id,
+    )
+    .await
+    .map_err(report_postgres_err)?
+    {
+        Some(x) if x.tournament_submission_id == candidate.tournament_submission_id => {}
+        _ => return Ok(false),
+    }
+
+    tournament_submission_service::add(
+        &mut sp,
+        candidate.creator_user_id,
+        candidate.submission_id,
+        candidate.tournament_id,
+        candidate.name.clone(),
+        TournamentSubmissionKind::Validate,
+        false,
+    )
+    .await
+    .map_err(report_postgres_err)?;
+
+    sp.commit().await.map_err(report_postgres_err)?;
+
+    Ok(true)
+}
+
 pub async fn tournament_submission_new(
+    app_data: AppData,
+    props: request::TournamentSubmissionNewProps,
+) -> Result<response::TournamentSubmission, response::ErrorResponse> {
+    // runs on its own task, so that the client going away can't stop it
+    // between committing the entry and queueing its matchups
+    tokio::task::spawn(add_tournament_submission(app_data, props))
+        .await
+        .map_err(report_join_err)?
+}
+
+async fn add_tournament_submission(
     AppData {
         db,
         auth_service,
+        run_code_service,
         matchup_task_tx,
+        queued_matches,
+        limits,
         ..
     }: AppData,
     props: request::TournamentSubmissionNewProps,
-) -> Result<response::TournamentSubmission, response::AppError> {
+) -> Result<response::TournamentSubmission, response::ErrorResponse> {
     // validate api key
     let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
     let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
 
-    let mut sp = con.transaction().await.map_err(report_postgres_err)?;
-
     // ensure that tournament exists
-    let tournament = tournament_service::get_by_tournament_id(&mut sp, props.tournament_id)
+    let tournament = tournament_service::get_by_tournament_id(con, props.tournament_id)
         .await
         .map_err(report_postgres_err)?
         .ok_or(response::AppError::TournamentNonexistent)?;
 
     // ensure that submission exists and belongs to you
-    let submission = submission_service::get_by_submission_id(&mut sp, props.submission_id)
+    let submission = submission_service::get_by_submission_id(con, props.submission_id)
         .await
         .map_err(report_postgres_err)?
         .ok_or(response::AppError::SubmissionNonexistent)?;
 
     // validate submission is owned by correct user or tournament creator
     if user.user_id != submission.creator_user_id && user.user_id != tournament.creator_user_id {
-        return Err(response::AppError::Unauthorized);
+        return Err(response::AppError::NoCapability.into());
     }
 
     // validate that the tournament isn't archived
     let tournament_data =
-        tournament_data_service::get_recent_by_tournament_id(&mut sp, props.tournament_id)
+        tournament_data_service::get_recent_by_tournament_id(con, props.tournament_id)
             .await
             .map_err(report_postgres_err)?
             .ok_or(response::AppError::TournamentNonexistent)?;
 
     if !tournament_data.active {
-        return Err(response::AppError::TournamentArchived);
+        return Err(response::AppError::TournamentArchived.into());
+    }
+
+    // validate that the tournament hasn't been finalized
+    if tournament_finalization_service::get_by_tournament_id(con, props.tournament_id)
+        .await
+        .map_err(report_postgres_err)?
+        .is_some()
+    {
+        return Err(response::AppError::TournamentFinalized.into());
+    }
+
+    // the tournament's import policy applies to the entries that play in it, but not to its testcases
+    // entries are only checked when they're made, so tightening the policy doesn't remove any,
+    // and one that's already playing, such as a validation entry going on to compete, isn't checked again
+    // this runs in the sandbox, so it's done before the transaction rather than while holding its locks
+    if !tournament_data.disallowed_imports.is_empty()
+        && (props.kind == TournamentSubmissionKind::Validate
+            || props.kind == TournamentSubmissionKind::Compete)
+    {
+        let prev_kind = tournament_submission_service::get_recent_by_tournament_submission(
+            con,
+            tournament.tournament_id,
+            submission.submission_id,
+        )
+        .await
+        .map_err(report_postgres_err)?
+        .map(|x| x.kind);
+
+        if !matches!(
+            prev_kind,
+            Some(TournamentSubmissionKind::Validate | TournamentSubmissionKind::Compete)
+        ) {
+            analysis::check_imports(
+                analyze_code(&run_code_service, &submission.code)
+                    .await
+                    .as_ref(),
+                &tournament_data.disallowed_imports,
+            )?;
+        }
     }
 
+    let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+    // serializes this with auto compete and other requests for the same entry,
+    // which would otherwise see the same previous entry and both queue its round robin
+    tournament_submission_service::lock_tournament_submission(
+        &mut sp,
+        tournament.tournament_id,
+        submission.submission_id,
+    )
+    .await
+    .map_err(report_postgres_err)?;
+
+    // matchups to queue once the tournament submission is committed
+    let mut tasks = vec![];
+
     match props.kind {
         request::TournamentSubmissionKind::Validate => {
             // only become validate if there was no submission earlier
@@ -694,20 +1838,20 @@ pub async fn tournament_submission_new(
                 .map_err(report_postgres_err)?
                 {
                     for i in 0..tournament_data.n_matchups {
-                        matchup_task_tx
-                            .send(MatchupTask {
-                                matchup_num: i,
-                                n_rounds: tournament_data.n_rounds,
-                                submission_id: submission.submission_id,
-                                opponent_submission_id: testcase.submission_id,
-                            })
-                            .unwrap();
+                        tasks.push(MatchupTask {
+                            tournament_id: tournament.tournament_id,
+                            matchup_num: i,
+                            n_rounds: tournament_data.n_rounds,
+                            submission_id: submission.submission_id,
+                            opponent_submission_id: testcase.submission_id,
+                            tournament_finalization_id: None,
+                            budget_user_id: Some(user.user_id),
+                        });
                     }
                 }
             }
         }
         request::TournamentSubmissionKind::Compete => {
-            // ensure validation entry exists
             let prev_submission =
                 tournament_submission_service::get_recent_by_tournament_submission(
                     &mut sp,
@@ -715,100 +1859,47 @@ pub async fn tournament_submission_new(
                     submission.submission_id,
                 )
                 .await
-                .map_err(report_postgres_err)?
-                .ok_or(AppError::TournamentSubmissionNotValidated)?;
+                .map_err(report_postgres_err)?;
 
-            if prev_submission.kind == TournamentSubmissionKind::Cancel
-                || prev_submission.kind == TournamentSubmissionKind::Testcase
-            {
-                return Err(AppError::TournamentSubmissionNotValidated);
-            }
-
-            // if changing from validate to compete then do this
-            if prev_submission.kind == TournamentSubmissionKind::Validate {
-                // ensure that testcases have all passed
-                for testcase in tournament_submission_service::get_recent_by_kind(
-                    &mut sp,
-                    props.tournament_id,
-                    &[request::TournamentSubmissionKind::Testcase],
-                )
-                .await
-                .map_err(report_postgres_err)?
-                {
-                    let mut testcase_results = vec![];
-                    // there can't be any errors when the submission was judging the testcase
-                    testcase_results.append(
-                        &mut match_resolution_service::get_recent_by_submission(
-                            &mut sp,
-                            props.submission_id,
-                            testcase.submission_id,
-                        )
-                        .await
-                        .map_err(report_postgres_err)?,
-                    );
-                    // there can't be any errors when the testcase was judging the submission
-                    testcase_results.append(
-                        &mut match_resolution_service::get_recent_by_submission(
-                            &mut sp,
-                            testcase.submission_id,
-                            props.submission_id,
-                        )
-                        .await
-                        .map_err(report_postgres_err)?,
-                    );
-
-                    // ensure that there are at least tournament_data
-                    if testcase_results.len()
-                        < (tournament_data.n_rounds * tournament_data.n_matchups * 2) as usize
-                    {
-                        return Err(AppError::TournamentSubmissionTestcaseIncomplete);
-                    }
-
-                    for result in testcase_results {
-                        if result.defected.is_none() {
-                            return Err(AppError::TournamentSubmissionTestcaseFails);
-                        }
-                    }
-                }
-
-                // then do match for all other submission entries
-                for opponent in tournament_submission_service::get_recent_by_kind(
-                    &mut sp,
-                    props.tournament_id,
-                    &[request::TournamentSubmissionKind::Compete],
-                )
-                .await
-                .map_err(report_postgres_err)?
-                {
-                    for i in 0..tournament_data.n_matchups {
-                        matchup_task_tx
-                            .send(MatchupTask {
-                                matchup_num: i,
-                                n_rounds: tournament_data.n_rounds,
-                                submission_id: submission.submission_id,
-                                opponent_submission_id: opponent.submission_id,
-                            })
-                            .unwrap();
-                    }
-                }
-
-                // also matchup against self
-                for i in 0..tournament_data.n_matchups {
-                    matchup_task_tx
-                        .send(MatchupTask {
-                            matchup_num: i,
-                            n_rounds: tournament_data.n_rounds,
-                            submission_id: submission.submission_id,
-                            opponent_submission_id: submission.submission_id,
-                        })
-                        .unwrap();
-                }
-            }
-        }
-        request::TournamentSubmissionKind::Testcase => {
-            // if requester isn't creator of the tournament, reject
-            if user.user_id != tournament.creator_user_id {
-                return Err(response::AppError::Unauthorized);
+            // reference submissions are known to work, so they don't have to validate
+            // only the tournament creator can enter them, since they don't own them
+            let is_reference = !reference_submission_service::get_by_submission_ids(
+                &mut sp,
+                &[submission.submission_id],
+            )
+            .await
+            .map_err(report_postgres_err)?
+            .is_empty();
+
+            match prev_submission.map(|x| x.kind) {
+                // already competing
+                Some(TournamentSubmissionKind::Compete) => {}
+                _ if is_reference => {
+                    // tournament organizers aren't subject to the match budget:
+                    // these tasks have no budget_user_id, so reserve_match_budget neither checks nor charges them
+                    tasks =
+                        compete_matchups(&mut sp, &tournament_data, submission.submission_id, None)
+                            .await?;
+                }
+                // if changing from validate to compete then do this
+                Some(TournamentSubmissionKind::Validate) => {
+                    tasks = compete_tasks(
+                        &mut sp,
+                        &tournament_data,
+                        submission.submission_id,
+                        user.user_id,
+                        &limits,
+                    )
+                    .await?;
+                }
+                // ensure validation entry exists
+                _ => return Err(AppError::TournamentSubmissionNotValidated.into()),
+            }
+        }
+        request::TournamentSubmissionKind::Testcase => {
+            // if requester isn't creator of the tournament, reject
+            if user.user_id != tournament.creator_user_id {
+                return Err(response::AppError::NoCapability.into());
             }
 
             // then do match for all validation and competition entries
@@ -830,14 +1921,16 @@ pub async fn tournament_submission_new(
                         .ok_or(AppError::SubmissionNonexistent)?;
 
                 for i in 0..tournament_data.n_matchups {
-                    matchup_task_tx
-                        .send(MatchupTask {
-                            matchup_num: i,
-                            n_rounds: tournament_data.n_rounds,
-                            submission_id: submission.submission_id,
-                            opponent_submission_id: opponent_submission.submission_id,
-                        })
-                        .unwrap();
+                    tasks.push(MatchupTask {
+                        tournament_id: tournament.tournament_id,
+                        matchup_num: i,
+                        n_rounds: tournament_data.n_rounds,
+                        submission_id: submission.submission_id,
+                        opponent_submission_id: opponent_submission.submission_id,
+                        tournament_finalization_id: None,
+                        // tournament organizers aren't subject to the match budget
+                        budget_user_id: None,
+                    });
                 }
             }
         }
@@ -846,6 +1939,8 @@ pub async fn tournament_submission_new(
         }
     }
 
+    let reservation = reserve_match_budget(&queued_matches, user.user_id, &tasks, &limits)?;
+
     // create tournament submission
     let tournament_submission = tournament_submission_service::add(
         &mut sp,
@@ -853,15 +1948,330 @@ pub async fn tournament_submission_new(
         submission.submission_id,
         tournament.tournament_id,
         props.name,
-        props.kind,
+        props.kind.clone(),
+        // only validation entries wait to compete
+        props.auto_compete && props.kind == TournamentSubmissionKind::Validate,
     )
     .await
     .map_err(report_postgres_err)?;
 
     sp.commit().await.map_err(report_postgres_err)?;
 
+    // an auto compete entry with no testcase rounds to wait on can compete right away
+    let waiting = tournament_submission.auto_compete && tasks.is_empty();
+
+    // queue the matchups
+    reservation.hand_over();
+    for task in tasks {
+        matchup_task_tx.send(task).unwrap();
+    }
+
+    if waiting {
+        promote_auto_compete(
+            con,
+            &matchup_task_tx,
+            &queued_matches,
+            &limits,
+            vec![tournament_submission.clone()],
+        )
+        .await;
+    }
+
+    // return json
+    // subscribers are notified by the insert trigger, which only fires once this has committed
+    Ok(fill_tournament_submission(con, tournament_submission).await?)
+}
+
+pub async fn tournament_submission_new_reference(
+    app_data: AppData,
+    props: request::TournamentSubmissionNewReferenceProps,
+) -> Result<response::TournamentSubmission, response::ErrorResponse> {
+    let reference_submission = {
+        let con: &mut tokio_postgres::Client =
+            &mut *app_data.db.get().await.map_err(report_pool_err)?;
+        reference_submission_service::get_recent_by_strategy(con, props.strategy.clone())
+            .await
+            .map_err(report_postgres_err)?
+            .ok_or(AppError::SubmissionNonexistent)?
+    };
+
+    // the submission belongs to nobody, so only the tournament creator gets past the ownership check
+    tournament_submission_new(
+        app_data,
+        request::TournamentSubmissionNewProps {
+            tournament_id: props.tournament_id,
+            submission_id: reference_submission.submission_id,
+            name: props
+                .name
+                .unwrap_or_else(|| strategies::name(&props.strategy).to_string()),
+            kind: props.kind,
+            auto_compete: false,
+            api_key: props.api_key,
+        },
+    )
+    .await
+}
+
+// Creates a reference submission for every strategy that doesn't have one with its current code.
+// Run after migrating, so a deployment always has the whole library.
+// Entries of replaced reference submissions keep playing the old code.
+pub async fn install_reference_submissions(
+    con: &mut tokio_postgres::Client,
+) -> Result<(), response::AppError> {
+    let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+    // so that instances booting at the same time don't both install a strategy
+    reference_submission_service::lock(&mut sp)
+        .await
+        .map_err(report_postgres_err)?;
+
+    for strategy in strategies::ALL {
+        let code = strategies::code(strategy);
+
+        let recent =
+            reference_submission_service::get_recent_by_strategy(&mut sp, strategy.clone())
+                .await
+                .map_err(report_postgres_err)?;
+
+        if let Some(recent) = recent {
+            let recent_submission =
+                submission_service::get_by_submission_id(&mut sp, recent.submission_id)
+                    .await
+                    .map_err(report_postgres_err)?
+                    .ok_or(response::AppError::SubmissionNonexistent)?;
+            // already up to date
+            if recent_submission.code == code {
+                continue;
+            }
+        }
+
+        let submission =
+            submission_service::add(&mut sp, REFERENCE_CREATOR_USER_ID, code.to_string())
+                .await
+                .map_err(report_postgres_err)?;
+        reference_submission_service::add(&mut sp, submission.submission_id, strategy.clone())
+            .await
+            .map_err(report_postgres_err)?;
+
+        utils::log(utils::Event {
+            msg: format!(
+                "installed reference submission {} for {}",
+                submission.submission_id,
+                strategy.as_ref()
+            ),
+            source: None::<String>,
+            severity: utils::SeverityKind::Info,
+        });
+    }
+
+    sp.commit().await.map_err(report_postgres_err)?;
+
+    Ok(())
+}
+
+pub async fn tournament_submission_validation_report(
+    AppData {
+        db, auth_service, ..
+    }: AppData,
+    props: request::TournamentSubmissionValidationReportProps,
+) -> Result<response::ValidationReport, response::AppError> {
+    // validate api key
+    let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+    let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
+
+    let tournament = tournament_service::get_by_tournament_id(con, props.tournament_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::AppError::TournamentNonexistent)?;
+
+    let submission = submission_service::get_by_submission_id(con, props.submission_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::AppError::SubmissionNonexistent)?;
+
+    // only the submission's owner and the tournament creator can see its output
+    if user.user_id != submission.creator_user_id && user.user_id != tournament.creator_user_id {
+        return Err(response::AppError::NoCapability);
+    }
+
+    let tournament_data =
+        tournament_data_service::get_recent_by_tournament_id(con, props.tournament_id)
+            .await
+            .map_err(report_postgres_err)?
+            .ok_or(response::AppError::TournamentNonexistent)?;
+
+    // the same number that tournament_submission_new requires
+    let n_expected_rounds = tournament_data.n_rounds * tournament_data.n_matchups * 2;
+
+    let mut testcases = vec![];
+    for (testcase, testcase_results) in
+        get_testcase_results(con, props.tournament_id, props.submission_id).await?
+    {
+        let mut failures = vec![];
+        for x in testcase_results.iter().filter(|x| x.defected.is_none()) {
+            // the output may have been pruned
+            let stderr = match_resolution_output_service::get_by_match_resolution_id(
+                con,
+                x.match_resolution_id,
+            )
+            .await
+            .map_err(report_postgres_err)?
+            .map(|x| x.stderr)
+            .unwrap_or_default();
+
+            failures.push(response::ValidationFailure {
+                match_resolution_id: x.match_resolution_id,
+                submission_id: x.submission_id,
+                opponent_submission_id: x.opponent_submission_id,
+                matchup: x.matchup,
+                round: x.round,
+                stderr_excerpt: utils::tail(&stderr, STDERR_EXCERPT_LENGTH),
+            });
+        }
+
+        testcases.push(response::TestcaseReport {
+            testcase: fill_tournament_submission(con, testcase).await?,
+            n_completed_rounds: testcase_results.len() as i64,
+            n_expected_rounds,
+            failures,
+        });
+    }
+
+    Ok(response::ValidationReport {
+        tournament_id: props.tournament_id,
+        submission_id: props.submission_id,
+        passed: testcases
+            .iter()
+            .all(|x| x.n_completed_rounds >= x.n_expected_rounds && x.failures.is_empty()),
+        testcases,
+    })
+}
+
+pub async fn tournament_finalization_new(
+    AppData {
+        db,
+        auth_service,
+        matchup_task_tx,
+        ..
+    }: AppData,
+    props: request::TournamentFinalizationNewProps,
+) -> Result<response::TournamentFinalization, response::AppError> {
+    // validate api key
+    let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+    if props.n_rounds <= 0 {
+        return Err(AppError::TournamentDataNRoundsInvalid);
+    }
+    if props.n_matchups <= 0 {
+        return Err(AppError::TournamentDataNMatchupsInvalid);
+    }
+
+    if i64::saturating_mul(props.n_rounds, props.n_matchups) > 256 {
+        return Err(AppError::TournamentDataTooManyMatches);
+    }
+
+    let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
+
+    let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+    // ensure that tournament exists and belongs to you
+    // the row stays locked until commit, so concurrent finalizations wait for this one
+    let tournament =
+        tournament_service::get_by_tournament_id_for_update(&mut sp, props.tournament_id)
+            .await
+            .map_err(report_postgres_err)?
+            .ok_or(response::AppError::TournamentNonexistent)?;
+    // validate tournament is owned by correct user
+    if tournament.creator_user_id != user.user_id {
+        return Err(response::AppError::TournamentNonexistent);
+    }
+
+    // validate that the tournament isn't archived
+    let tournament_data =
+        tournament_data_service::get_recent_by_tournament_id(&mut sp, props.tournament_id)
+            .await
+            .map_err(report_postgres_err)?
+            .ok_or(response::AppError::TournamentNonexistent)?;
+
+    if !tournament_data.active {
+        return Err(response::AppError::TournamentArchived);
+    }
+
+    // a tournament can only be finalized once
+    if tournament_finalization_service::get_by_tournament_id(&mut sp, props.tournament_id)
+        .await
+        .map_err(report_postgres_err)?
+        .is_some()
+    {
+        return Err(response::AppError::TournamentFinalized);
+    }
+
+    // create tournament finalization
+    let tournament_finalization = tournament_finalization_service::add(
+        &mut sp,
+        user.user_id,
+        tournament.tournament_id,
+        props.n_rounds,
+        props.n_matchups,
+    )
+    .await
+    .map_err(|e| match e.code() {
+        // enforced by the database as well, in case a finalization got past the lock
+        Some(&tokio_postgres::error::SqlState::UNIQUE_VIOLATION) => {
+            response::AppError::TournamentFinalized
+        }
+        _ => report_postgres_err(e),
+    })?;
+
+    // snapshot the current compete entries
+    let mut compete_ids = vec![];
+    for competitor in tournament_submission_service::get_recent_by_kind(
+        &mut sp,
+        tournament.tournament_id,
+        &[TournamentSubmissionKind::Compete],
+    )
+    .await
+    .map_err(report_postgres_err)?
+    {
+        tournament_finalization_entry_service::add(
+            &mut sp,
+            tournament_finalization.tournament_finalization_id,
+            competitor.tournament_submission_id,
+            competitor.submission_id,
+            competitor.name,
+        )
+        .await
+        .map_err(report_postgres_err)?;
+
+        compete_ids.push(competitor.submission_id);
+    }
+
+    sp.commit().await.map_err(report_postgres_err)?;
+
+    // run a fresh round robin among the snapshotted entries
+    for i in 0..tournament_finalization.n_matchups {
+        for a in 0..compete_ids.len() {
+            for b in 0..=a {
+                matchup_task_tx
+                    .send(MatchupTask {
+                        tournament_id: tournament.tournament_id,
+                        matchup_num: i,
+                        n_rounds: tournament_finalization.n_rounds,
+                        submission_id: compete_ids[a],
+                        opponent_submission_id: compete_ids[b],
+                        tournament_finalization_id: Some(
+                            tournament_finalization.tournament_finalization_id,
+                        ),
+                        budget_user_id: None,
+                    })
+                    .unwrap();
+            }
+        }
+    }
+
     // return json
-    fill_tournament_submission(con, tournament_submission).await
+    fill_tournament_finalization(con, tournament_finalization).await
 }
 
 pub async fn submission_view(
@@ -869,353 +2279,1778 @@ pub async fn submission_view(
         db, auth_service, ..
     }: AppData,
     props: request::SubmissionViewProps,
-) -> Result<Vec<response::Submission>, response::AppError> {
+) -> Result<response::Page<response::Submission>, response::AppError> {
     // validate api key
     let user = get_user_if_api_key_valid(&auth_service, props.api_key.clone()).await?;
 
+    let limit = validate_page(props.limit, &props.cursor)?;
+
+    // only your own submissions are visible
+    let props = request::SubmissionViewProps {
+        creator_user_id: Some(match props.creator_user_id {
+            Some(creator_user_ids) => creator_user_ids
+                .into_iter()
+                .filter(|x| *x == user.user_id)
+                .collect(),
+            None => vec![user.user_id],
+        }),
+        limit: Some(limit),
+        ..props
+    };
+
     let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
     // get users
     let submissions = submission_service::query(con, props)
         .await
         .map_err(report_postgres_err)?;
 
+    let last_id = submissions.last().map(|x| x.submission_id);
+
     // return submissions
     let mut resp_submissions = vec![];
-    for u in submissions
-        .into_iter()
-        .filter(|u| u.creator_user_id == user.user_id)
-    {
+    for u in submissions.into_iter() {
         resp_submissions.push(fill_submission(con, u).await?);
     }
 
-    Ok(resp_submissions)
+    Ok(make_page(resp_submissions, limit, last_id))
+}
+
+// reference submissions can be viewed by anyone, unlike other submissions
+pub async fn reference_submission_view(
+    AppData { db, .. }: AppData,
+    props: request::ReferenceSubmissionViewProps,
+) -> Result<Vec<response::ReferenceSubmission>, response::AppError> {
+    let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
+
+    let reference_submissions = reference_submission_service::query(con, props.only_recent)
+        .await
+        .map_err(report_postgres_err)?;
+
+    let mut resp_reference_submissions = vec![];
+    for u in reference_submissions.into_iter() {
+        resp_reference_submissions.push(fill_reference_submission(con, u).await?);
+    }
+
+    Ok(resp_reference_submissions)
 }
 
 pub async fn tournament_data_view(
-    AppData {
-        db, auth_service, ..
-    }: AppData,
+    AppData { db, .. }: AppData,
     props: request::TournamentDataViewProps,
-) -> Result<Vec<response::TournamentData>, response::AppError> {
+) -> Result<response::Page<response::TournamentData>, response::AppError> {
+    let limit = validate_page(props.limit, &props.cursor)?;
+    let props = request::TournamentDataViewProps {
+        limit: Some(limit),
+        ..props
+    };
+
     let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
     // get users
     let tournament_data = tournament_data_service::query(con, props)
         .await
         .map_err(report_postgres_err)?;
 
+    let last_id = tournament_data.last().map(|x| x.tournament_data_id);
+
     // return tournament_datas
-    let mut resp_tournament_datas = vec![];
-    for u in tournament_data.into_iter() {
-        resp_tournament_datas.push(fill_tournament_data(con, u).await?);
-    }
+    let tournaments =
+        load_tournaments(con, tournament_data.iter().map(|x| x.tournament_id)).await?;
+    let resp_tournament_datas = tournament_data
+        .into_iter()
+        .map(|u| fill_tournament_data_with(&tournaments, u))
+        .collect::<Result<Vec<_>, _>>()?;
 
-    Ok(resp_tournament_datas)
+    Ok(make_page(resp_tournament_datas, limit, last_id))
 }
 
 pub async fn tournament_submission_view(
-    AppData {
-        db, auth_service, ..
-    }: AppData,
+    AppData { db, .. }: AppData,
     props: request::TournamentSubmissionViewProps,
-) -> Result<Vec<response::TournamentSubmission>, response::AppError> {
+) -> Result<response::Page<response::TournamentSubmission>, response::AppError> {
+    let limit = validate_page(props.limit, &props.cursor)?;
+    let props = request::TournamentSubmissionViewProps {
+        limit: Some(limit),
+        ..props
+    };
+
     let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
     // get users
     let tournament_submission = tournament_submission_service::query(con, props)
         .await
         .map_err(report_postgres_err)?;
 
+    let last_id = tournament_submission
+        .last()
+        .map(|x| x.tournament_submission_id);
+
     // return tournament_submissions
-    let mut resp_tournament_submissions = vec![];
-    for u in tournament_submission.into_iter() {
-        resp_tournament_submissions.push(fill_tournament_submission(con, u).await?);
-    }
+    let tournaments =
+        load_tournaments(con, tournament_submission.iter().map(|x| x.tournament_id)).await?;
+    let reference_strategies =
+        load_reference_strategies(con, tournament_submission.iter().map(|x| x.submission_id))
+            .await?;
+    let resp_tournament_submissions = tournament_submission
+        .into_iter()
+        .map(|u| fill_tournament_submission_with(&tournaments, &reference_strategies, u))
+        .collect::<Result<Vec<_>, _>>()?;
 
-    Ok(resp_tournament_submissions)
+    Ok(make_page(resp_tournament_submissions, limit, last_id))
 }
 
 pub async fn match_resolution_view(
-    AppData {
-        db, auth_service, ..
-    }: AppData,
+    AppData { db, .. }: AppData,
     props: request::MatchResolutionViewProps,
-) -> Result<Vec<response::MatchResolution>, response::AppError> {
+) -> Result<response::Page<response::MatchResolution>, response::AppError> {
+    let limit = validate_page(props.limit, &props.cursor)?;
+    let props = request::MatchResolutionViewProps {
+        limit: Some(limit),
+        ..props
+    };
+
     let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
     // get users
     let match_resolution = match_resolution_service::query(con, props)
         .await
         .map_err(report_postgres_err)?;
 
+    let last_id = match_resolution.last().map(|x| x.match_resolution_id);
+
     // return match_resolutions
     let mut resp_match_resolutions = vec![];
     for u in match_resolution.into_iter() {
         resp_match_resolutions.push(fill_match_resolution(con, u).await?);
     }
 
-    Ok(resp_match_resolutions)
+    Ok(make_page(resp_match_resolutions, limit, last_id))
 }
 
-pub async fn tournament_submission_stream(
+pub async fn match_resolution_output(
     AppData {
-        db,
-        auth_service,
-        tournament_submission_insert_tx,
-        ..
+        db, auth_service, ..
     }: AppData,
-    websocket: warp::ws::WebSocket,
-) {
-    tokio::pin!(websocket);
+    props: request::MatchResolutionOutputProps,
+) -> Result<response::MatchResolutionOutput, response::AppError> {
+    // validate api key
+    let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
 
-    // subscribe to sender
-    let mut rx = tournament_submission_insert_tx.subscribe();
+    let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
 
-    let result: Result<(), response::AppError> = try {
-        let message = websocket
-            .next()
+    let match_resolution =
+        match_resolution_service::get_by_match_resolution_id(con, props.match_resolution_id)
             .await
-            // socket closed cleanly
-            .ok_or(response::AppError::StreamEndBeforeRequest)?
-            // websocket errored out for some reason
-            .map_err(|_| response::AppError::StreamEndBeforeRequest)?;
-
-        let message_str = message
-            .to_str()
-            // message wasn't text
-            .map_err(|_| response::AppError::StreamEndBeforeRequest)?;
-
-        let props = serde_json::from_str::<request::TournamentSubmissionViewProps>(message_str)
-            .map_err(|_| response::AppError::DecodeError)?;
-
-        // validate that api key is valid
+            .map_err(report_postgres_err)?
+            .ok_or(response::AppError::MatchResolutionNonexistent)?;
 
-        // initialize the next novel id to the one specified
-        let mut next_new_id = props.min_id.unwrap_or(0);
+    // output can give away how a bot works, so only its author and the organizers it played for can read it
+    let submission = submission_service::get_by_submission_id(con, match_resolution.submission_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::AppError::SubmissionNonexistent)?;
 
-        // first we grab all existing ones
-        {
-            let client: &mut tokio_postgres::Client =
-                &mut *db.get().await.map_err(report_pool_err)?;
+    if submission.creator_user_id != user.user_id
+        && !tournament_service::is_creator_of_tournament_with_pair(
+            con,
+            user.user_id,
+            match_resolution.submission_id,
+            match_resolution.opponent_submission_id,
+        )
+        .await
+        .map_err(report_postgres_err)?
+    {
+        return Err(response::AppError::NoCapability);
+    }
+
+    let match_resolution_output =
+        match_resolution_output_service::get_by_match_resolution_id(con, props.match_resolution_id)
+            .await
+            .map_err(report_postgres_err)?
+            .ok_or(response::AppError::MatchResolutionOutputPruned)?;
+
+    Ok(response::MatchResolutionOutput {
+        match_resolution_id: match_resolution_output.match_resolution_id,
+        stdout: match_resolution_output.stdout,
+        stdout_truncated: match_resolution_output.stdout_truncated,
+        stderr: match_resolution_output.stderr,
+        stderr_truncated: match_resolution_output.stderr_truncated,
+    })
+}
+
+pub async fn tournament_detail(
+    AppData { db, .. }: AppData,
+    props: request::TournamentDetailProps,
+) -> Result<response::TournamentDetail, response::AppError> {
+    let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
+
+    let tournament_data =
+        tournament_data_service::get_recent_by_tournament_id(con, props.tournament_id)
+            .await
+            .map_err(report_postgres_err)?
+            .ok_or(response::AppError::TournamentNonexistent)?;
+
+    let tournament_submissions = tournament_submission_service::get_recent_by_kind(
+        con,
+        props.tournament_id,
+        &[
+            TournamentSubmissionKind::Compete,
+            TournamentSubmissionKind::Validate,
+            TournamentSubmissionKind::Testcase,
+            TournamentSubmissionKind::Cancel,
+        ],
+    )
+    .await
+    .map_err(report_postgres_err)?;
+
+    let tournament_finalization =
+        tournament_finalization_service::get_by_tournament_id(con, props.tournament_id)
+            .await
+            .map_err(report_postgres_err)?;
+
+    // everything here belongs to the same tournament
+    let tournaments = load_tournaments(con, [props.tournament_id]).await?;
+    let reference_strategies =
+        load_reference_strategies(con, tournament_submissions.iter().map(|x| x.submission_id))
+            .await?;
+
+    let count_kind = |kind: TournamentSubmissionKind| {
+        tournament_submissions
+            .iter()
+            .filter(|x| x.kind == kind)
+            .count() as i64
+    };
+
+    let summary = response::TournamentSummary {
+        n_compete: count_kind(TournamentSubmissionKind::Compete),
+        n_validate: count_kind(TournamentSubmissionKind::Validate),
+        n_testcase: count_kind(TournamentSubmissionKind::Testcase),
+        n_cancel: count_kind(TournamentSubmissionKind::Cancel),
+        finalized: tournament_finalization.is_some(),
+    };
+
+    Ok(response::TournamentDetail {
+        tournament_data: fill_tournament_data_with(&tournaments, tournament_data)?,
+        tournament_submissions: tournament_submissions
+            .into_iter()
+            .map(|u| fill_tournament_submission_with(&tournaments, &reference_strategies, u))
+            .collect::<Result<Vec<_>, _>>()?,
+        tournament_finalization: tournament_finalization
+            .map(|u| fill_tournament_finalization_with(&tournaments, u))
+            .transpose()?,
+        summary,
+    })
+}
+
+// collects everything needed to recreate the tournament elsewhere
+pub async fn export_tournament(
+    con: &mut impl GenericClient,
+    tournament_id: i64,
+) -> Result<archive::Archive, response::AppError> {
+    let tournament = tournament_service::get_by_tournament_id(con, tournament_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::AppError::TournamentNonexistent)?;
+
+    let tournament_data = tournament_data_service::get_by_tournament_id(con, tournament_id)
+        .await
+        .map_err(report_postgres_err)?;
+
+    let tournament_submissions =
+        tournament_submission_service::get_by_tournament_id(con, tournament_id)
+            .await
+            .map_err(report_postgres_err)?;
+
+    let mut submission_ids: Vec<i64> = tournament_submissions
+        .iter()
+        .map(|x| x.submission_id)
+        .collect();
+    submission_ids.sort();
+    submission_ids.dedup();
+
+    let submissions = submission_service::get_by_submission_ids(con, &submission_ids)
+        .await
+        .map_err(report_postgres_err)?;
+
+    let tournament_finalization =
+        tournament_finalization_service::get_by_tournament_id(con, tournament_id)
+            .await
+            .map_err(report_postgres_err)?;
+
+    let tournament_finalization_entries = match &tournament_finalization {
+        Some(tournament_finalization) => {
+            tournament_finalization_entry_service::get_by_tournament_finalization_id(
+                con,
+                tournament_finalization.tournament_finalization_id,
+            )
+            .await
+            .map_err(report_postgres_err)?
+        }
+        None => vec![],
+    };
+
+    let match_resolutions = match_resolution_service::get_by_submission_ids(
+        con,
+        &submission_ids,
+        tournament_finalization
+            .as_ref()
+            .map(|x| x.tournament_finalization_id),
+    )
+    .await
+    .map_err(report_postgres_err)?;
+
+    Ok(archive::Archive {
+        version: archive::ARCHIVE_VERSION,
+        export_time: utils::current_time_millis(),
+        anonymized: false,
+        tournament,
+        tournament_data,
+        submissions,
+        tournament_submissions,
+        tournament_finalization,
+        tournament_finalization_entries,
+        match_resolutions,
+    })
+}
+
+// recreates an exported tournament, giving every row a new id
+// user ids from another deployment mean nothing here, so everything is owned by creator_user_id
+pub async fn import_tournament(
+    con: &mut tokio_postgres::Client,
+    archive: archive::Archive,
+    creator_user_id: i64,
+) -> Result<Tournament, response::AppError> {
+    if archive.version < 1 || archive.version > archive::ARCHIVE_VERSION {
+        return Err(response::AppError::ArchiveVersionUnsupported);
+    }
+
+    let mut sp = con.transaction().await.map_err(report_postgres_err)?;
+
+    let tournament = tournament_service::restore(
+        &mut sp,
+        Tournament {
+            creator_user_id,
+            ..archive.tournament
+        },
+    )
+    .await
+    .map_err(report_postgres_err)?;
+
+    // maps from the ids in the archive to the new ids
+    let mut submission_ids = HashMap::new();
+    let mut tournament_submission_ids = HashMap::new();
+
+    for tournament_data in archive.tournament_data {
+        tournament_data_service::restore(
+            &mut sp,
+            TournamentData {
+                creator_user_id,
+                tournament_id: tournament.tournament_id,
+                ..tournament_data
+            },
+        )
+        .await
+        .map_err(report_postgres_err)?;
+    }
+
+    for submission in archive.submissions {
+        let old_submission_id = submission.submission_id;
+        let submission = submission_service::restore(
+            &mut sp,
+            Submission {
+                creator_user_id,
+                ..submission
+            },
+        )
+        .await
+        .map_err(report_postgres_err)?;
+        submission_ids.insert(old_submission_id, submission.submission_id);
+    }
+
+    let new_submission_id = |submission_id: i64| {
+        submission_ids
+            .get(&submission_id)
+            .copied()
+            .ok_or(response::AppError::ArchiveInvalid)
+    };
+
+    for tournament_submission in archive.tournament_submissions {
+        let old_tournament_submission_id = tournament_submission.tournament_submission_id;
+        let tournament_submission = tournament_submission_service::restore(
+            &mut sp,
+            TournamentSubmission {
+                creator_user_id,
+                tournament_id: tournament.tournament_id,
+                submission_id: new_submission_id(tournament_submission.submission_id)?,
+                ..tournament_submission
+            },
+        )
+        .await
+        .map_err(report_postgres_err)?;
+        tournament_submission_ids.insert(
+            old_tournament_submission_id,
+            tournament_submission.tournament_submission_id,
+        );
+    }
+
+    let tournament_finalization_id = match archive.tournament_finalization {
+        Some(tournament_finalization) => {
+            let old_tournament_finalization_id = tournament_finalization.tournament_finalization_id;
+            let tournament_finalization = tournament_finalization_service::restore(
+                &mut sp,
+                TournamentFinalization {
+                    creator_user_id,
+                    tournament_id: tournament.tournament_id,
+                    ..tournament_finalization
+                },
+            )
+            .await
+            .map_err(report_postgres_err)?;
+            Some((
+                old_tournament_finalization_id,
+                tournament_finalization.tournament_finalization_id,
+            ))
+        }
+        None => None,
+    };
+
+    let new_tournament_finalization_id = |id: Option<i64>| match (id, tournament_finalization_id) {
+        (None, _) => Ok(None),
+        (Some(id), Some((old, new))) if id == old => Ok(Some(new)),
+        _ => Err(response::AppError::ArchiveInvalid),
+    };
+
+    for tournament_finalization_entry in archive.tournament_finalization_entries {
+        tournament_finalization_entry_service::restore(
+            &mut sp,
+            TournamentFinalizationEntry {
+                tournament_finalization_id: new_tournament_finalization_id(Some(
+                    tournament_finalization_entry.tournament_finalization_id,
+                ))?
+                .ok_or(response::AppError::ArchiveInvalid)?,
+                tournament_submission_id: tournament_submission_ids
+                    .get(&tournament_finalization_entry.tournament_submission_id)
+                    .copied()
+                    .ok_or(response::AppError::ArchiveInvalid)?,
+                submission_id: new_submission_id(tournament_finalization_entry.submission_id)?,
+                ..tournament_finalization_entry
+            },
+        )
+        .await
+        .map_err(report_postgres_err)?;
+    }
+
+    for match_resolution in archive.match_resolutions {
+        match_resolution_service::restore(
+            &mut sp,
+            MatchResolution {
+                submission_id: new_submission_id(match_resolution.submission_id)?,
+                opponent_submission_id: new_submission_id(match_resolution.opponent_submission_id)?,
+                tournament_finalization_id: new_tournament_finalization_id(
+                    match_resolution.tournament_finalization_id,
+                )?,
+                ..match_resolution
+            },
+        )
+        .await
+        .map_err(report_postgres_err)?;
+    }
+
+    sp.commit().await.map_err(report_postgres_err)?;
+
+    Ok(tournament)
+}
+
+pub async fn tournament_export(
+    AppData {
+        db, auth_service, ..
+    }: AppData,
+    props: request::TournamentExportProps,
+) -> Result<archive::Archive, response::AppError> {
+    // validate api key
+    let user = get_user_if_api_key_valid(&auth_service, props.api_key).await?;
+
+    let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
+
+    let tournament = tournament_service::get_by_tournament_id(con, props.tournament_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::AppError::TournamentNonexistent)?;
+
+    // the archive contains everyone's code, so only the tournament creator can export it
+    if user.user_id != tournament.creator_user_id {
+        return Err(response::AppError::NoCapability);
+    }
+
+    let archive = export_tournament(con, props.tournament_id).await?;
+
+    Ok(if props.anonymize {
+        archive.anonymize()
+    } else {
+        archive
+    })
+}
+
+pub async fn tournament_finalization_view(
+    AppData { db, .. }: AppData,
+    props: request::TournamentFinalizationViewProps,
+) -> Result<response::Page<response::TournamentFinalization>, response::AppError> {
+    let limit = validate_page(props.limit, &props.cursor)?;
+    let props = request::TournamentFinalizationViewProps {
+        limit: Some(limit),
+        ..props
+    };
+
+    let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
+    // get tournament finalizations
+    let tournament_finalization = tournament_finalization_service::query(con, props)
+        .await
+        .map_err(report_postgres_err)?;
+
+    let last_id = tournament_finalization
+        .last()
+        .map(|x| x.tournament_finalization_id);
+
+    // return tournament_finalizations
+    let tournaments =
+        load_tournaments(con, tournament_finalization.iter().map(|x| x.tournament_id)).await?;
+    let resp_tournament_finalizations = tournament_finalization
+        .into_iter()
+        .map(|u| fill_tournament_finalization_with(&tournaments, u))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(make_page(resp_tournament_finalizations, limit, last_id))
+}
+
+pub async fn tournament_finalization_entry_view(
+    AppData { db, .. }: AppData,
+    props: request::TournamentFinalizationEntryViewProps,
+) -> Result<response::Page<response::TournamentFinalizationEntry>, response::AppError> {
+    let limit = validate_page(props.limit, &props.cursor)?;
+    let props = request::TournamentFinalizationEntryViewProps {
+        limit: Some(limit),
+        ..props
+    };
+
+    let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
+    // get tournament finalization entries
+    let tournament_finalization_entry = tournament_finalization_entry_service::query(con, props)
+        .await
+        .map_err(report_postgres_err)?;
+
+    let last_id = tournament_finalization_entry
+        .last()
+        .map(|x| x.tournament_finalization_entry_id);
+
+    // return tournament_finalization_entries
+    let mut resp_tournament_finalization_entries = vec![];
+    for u in tournament_finalization_entry.into_iter() {
+        resp_tournament_finalization_entries
+            .push(fill_tournament_finalization_entry(con, u).await?);
+    }
+
+    Ok(make_page(
+        resp_tournament_finalization_entries,
+        limit,
+        last_id,
+    ))
+}
+
+// GET /public/v1/tournaments
+pub async fn v1_tournaments(
+    app_data: AppData,
+    _: (),
+    props: request::TournamentDataViewProps,
+    api_key: String,
+) -> Result<response::Page<response::TournamentData>, response::AppError> {
+    tournament_data_view(
+        app_data,
+        request::TournamentDataViewProps { api_key, ..props },
+    )
+    .await
+}
+
+// GET /public/v1/tournaments/{tournament_id}
+pub async fn v1_tournament(
+    app_data: AppData,
+    tournament_id: i64,
+    _: request::NoQueryProps,
+    api_key: String,
+) -> Result<response::TournamentDetail, response::AppError> {
+    tournament_detail(
+        app_data,
+        request::TournamentDetailProps {
+            tournament_id,
+            api_key,
+        },
+    )
+    .await
+}
+
+// GET /public/v1/tournaments/{tournament_id}/data
+pub async fn v1_tournament_data(
+    app_data: AppData,
+    tournament_id: i64,
+    props: request::TournamentDataViewProps,
+    api_key: String,
+) -> Result<response::Page<response::TournamentData>, response::AppError> {
+    tournament_data_view(
+        app_data,
+        request::TournamentDataViewProps {
+            tournament_id: Some(vec![tournament_id]),
+            api_key,
+            ..props
+        },
+    )
+    .await
+}
+
+// GET /public/v1/tournaments/{tournament_id}/submissions
+pub async fn v1_tournament_submissions(
+    app_data: AppData,
+    tournament_id: i64,
+    props: request::TournamentSubmissionViewProps,
+    api_key: String,
+) -> Result<response::Page<response::TournamentSubmission>, response::AppError> {
+    tournament_submission_view(
+        app_data,
+        request::TournamentSubmissionViewProps {
+            tournament_id: Some(vec![tournament_id]),
+            api_key,
+            ..props
+        },
+    )
+    .await
+}
+
+// GET /public/v1/submissions/{submission_id}
+pub async fn v1_submission(
+    app_data: AppData,
+    submission_id: i64,
+    _: request::NoQueryProps,
+    api_key: String,
+) -> Result<response::Submission, response::AppError> {
+    submission_view(
+        app_data,
+        request::SubmissionViewProps {
+            submission_id: Some(vec![submission_id]),
+            min_creation_time: None,
+            max_creation_time: None,
+            creator_user_id: None,
+            limit: None,
+            cursor: None,
+            api_key,
+        },
+    )
+    .await?
+    .items
+    .pop()
+    .ok_or(response::AppError::SubmissionNonexistent)
+}
+
+// GET /public/v1/match_resolutions
+pub async fn v1_match_resolutions(
+    app_data: AppData,
+    _: (),
+    props: request::MatchResolutionViewProps,
+    api_key: String,
+) -> Result<response::Page<response::MatchResolution>, response::AppError> {
+    match_resolution_view(
+        app_data,
+        request::MatchResolutionViewProps { api_key, ..props },
+    )
+    .await
+}
+
+// GET /public/v1/match_resolutions/{match_resolution_id}/output
+pub async fn v1_match_resolution_output(
+    app_data: AppData,
+    match_resolution_id: i64,
+    _: request::NoQueryProps,
+    api_key: String,
+) -> Result<response::MatchResolutionOutput, response::AppError> {
+    match_resolution_output(
+        app_data,
+        request::MatchResolutionOutputProps {
+            match_resolution_id,
+            api_key,
+        },
+    )
+    .await
+}
+
+pub type ResultsStream = Pin<Box<dyn Stream<Item = Result<String, response::AppError>> + Send>>;
+
+// a file of match results, sent as it's read from the db
+pub struct ResultsDownload {
+    pub format: request::ResultsFormat,
+    pub filename: String,
+    pub lines: ResultsStream,
+}
+
+static RESULTS_CSV_HEADER: &str = "tournamentId,tournamentTitle,tournamentFinalizationId,submissionId,submissionName,opponentSubmissionId,opponentSubmissionName,matchup,round,defected,opponentDefected,payoff,opponentPayoff\n";
+
+fn results_csv_record(x: &response::MatchResult) -> String {
+    fn opt<T: ToString>(x: Option<T>) -> String {
+        x.map(|x| x.to_string()).unwrap_or_default()
+    }
+    [
+        x.tournament_id.to_string(),
+        utils::csv_field(&x.tournament_title),
+        opt(x.tournament_finalization_id),
+        x.submission_id.to_string(),
+        utils::csv_field(&x.submission_name),
+        x.opponent_submission_id.to_string(),
+        utils::csv_field(&x.opponent_submission_name),
+        x.matchup.to_string(),
+        x.round.to_string(),
+        opt(x.defected),
+        opt(x.opponent_defected),
+        opt(x.payoff),
+        opt(x.opponent_payoff),
+    ]
+    .join(",")
+        + "\n"
+}
+
+fn results_line(format: &request::ResultsFormat, x: &response::MatchResult) -> String {
+    match format {
+        request::ResultsFormat::Csv => results_csv_record(x),
+        request::ResultsFormat::Ndjson => {
+            serde_json::to_string(x).expect("serde should have serialized json") + "\n"
+        }
+    }
+}
+
+// GET /public/v1/tournaments/{tournament_id}/results
+// Every recent round played between the tournament's submissions, as CSV or NDJSON.
+// Only the entry names are held in memory: rows are written out as postgres returns them.
+pub async fn v1_tournament_results(
+    AppData {
+        db,
+        results_downloads,
+        auth_service,
+        ..
+    }: AppData,
+    tournament_id: i64,
+    props: request::TournamentResultsProps,
+    api_key: String,
+) -> Result<ResultsDownload, response::AppError> {
+    // validate api key
+    let _ = get_user_if_api_key_valid(&auth_service, api_key).await?;
+
+    // the rows are read as they're sent, which lasts as long as the client takes to download them,
+    // so the connection is held until then and downloads can't take over the pool
+    let permit = results_downloads
+        .try_acquire_owned()
+        .map_err(|_| response::AppError::RateLimited)?;
+
+    let mut client = db.get().await.map_err(report_pool_err)?;
+    let con: &mut tokio_postgres::Client = &mut client;
+
+    let tournament_data = tournament_data_service::get_recent_by_tournament_id(con, tournament_id)
+        .await
+        .map_err(report_postgres_err)?
+        .ok_or(response::AppError::TournamentNonexistent)?;
+
+    // entries are named by the final evaluation if there is one, otherwise by their most recent entry
+    let names: HashMap<i64, String> = match props.tournament_finalization_id {
+        Some(tournament_finalization_id) => {
+            tournament_finalization_service::get_by_tournament_id(con, tournament_id)
+                .await
+                .map_err(report_postgres_err)?
+                .filter(|x| x.tournament_finalization_id == tournament_finalization_id)
+                .ok_or(response::AppError::TournamentFinalizationNonexistent)?;
+
+            tournament_finalization_entry_service::get_by_tournament_finalization_id(
+                con,
+                tournament_finalization_id,
+            )
+            .await
+            .map_err(report_postgres_err)?
+            .into_iter()
+            .map(|x| (x.submission_id, x.name))
+            .collect()
+        }
+        // oldest first, so later entries overwrite earlier names
+        None => tournament_submission_service::get_by_tournament_id(con, tournament_id)
+            .await
+            .map_err(report_postgres_err)?
+            .into_iter()
+            .map(|x| (x.submission_id, x.name))
+            .collect(),
+    };
+
+    let submission_ids: Vec<i64> = names.keys().copied().collect();
+
+    let mut rounds = match_resolution_service::stream_rounds(
+        con,
+        &submission_ids,
+        props.tournament_finalization_id,
+    )
+    .await
+    .map_err(report_postgres_err)?;
+
+    let format = props.format;
+    let (tx, lines) = mpsc::channel(64);
+
+    tokio::task::spawn({
+        let format = format.clone();
+        async move {
+            // both go back once the download finishes or the client goes away
+            let _client = client;
+            let _permit = permit;
+
+            if format == request::ResultsFormat::Csv
+                && tx.send(Ok(RESULTS_CSV_HEADER.to_owned())).await.is_err()
+            {
+                return;
+            }
+
+            while let Some(round) = rounds.next().await {
+                let line = round.map_err(report_postgres_err).map(|round| {
+                    // every round is between entries, but a name missing shouldn't take the download down
+                    let name = |submission_id: i64| {
+                        names
+                            .get(&submission_id)
+                            .cloned()
+                            .unwrap_or_else(|| submission_id.to_string())
+                    };
+                    let payoff = |defected: Option<bool>, opponent_defected: Option<bool>| {
+                        Some(standings::score_prisoners_dilemma(
+                            defected?,
+                            opponent_defected?,
+                        ))
+                    };
+                    results_line(
+                        &format,
+                        &response::MatchResult {
+                            tournament_id,
+                            tournament_title: tournament_data.title.clone(),
+                            tournament_finalization_id: round.tournament_finalization_id,
+                            submission_id: round.submission_id,
+                            submission_name: name(round.submission_id),
+                            opponent_submission_id: round.opponent_submission_id,
+                            opponent_submission_name: name(round.opponent_submission_id),
+                            matchup: round.matchup,
+                            round: round.round,
+                            defected: round.defected,
+                            opponent_defected: round.opponent_defected,
+                            payoff: payoff(round.defected, round.opponent_defected),
+                            opponent_payoff: payoff(round.opponent_defected, round.defected),
+                        },
+                    )
+                });
+                // an error aborts the response, so a partial download can't be mistaken for a whole one
+                let failed = line.is_err();
+                // the client went away
+                if tx.send(line).await.is_err() || failed {
+                    return;
+                }
+            }
+        }
+    });
+
+    let extension = match format {
+        request::ResultsFormat::Csv => "csv",
+        request::ResultsFormat::Ndjson => "ndjson",
+    };
+
+    Ok(ResultsDownload {
+        format,
+        filename: format!("tournament-{}-results.{}", tournament_id, extension),
+        // the task above stops once this stream is dropped
+        lines: Box::pin(futures_util::stream::unfold(
+            lines,
+            |mut lines| async move { lines.recv().await.map(|line| (line, lines)) },
+        )),
+    })
+}
+
+// a view that can be followed live: first a snapshot from the db, then broadcast inserts
+pub trait StreamView: Clone + Send + Sync + serde::de::DeserializeOwned + 'static {
+    type Item: Clone + Send + serde::Serialize + 'static;
+    // the id that items are ordered by
+    fn item_id(item: &Self::Item) -> i64;
+    fn item_creation_time(item: &Self::Item) -> i64;
+    // whether a broadcast item would have been returned by the view
+    fn matches(&self, item: &Self::Item) -> bool;
+    // restricts the view to items created at or after min_creation_time
+    fn with_min_creation_time(self, min_creation_time: i64) -> Self;
+    // queries the whole view from the db, ignoring pagination
+    fn snapshot(
+        self,
+        db: Db,
+    ) -> impl Future<Output = Result<Vec<Self::Item>, response::AppError>> + Send;
+}
+
+impl StreamView for request::TournamentDataViewProps {
+    type Item = response::TournamentData;
+
+    fn item_id(item: &Self::Item) -> i64 {
+        item.tournament_data_id
+    }
+
+    fn item_creation_time(item: &Self::Item) -> i64 {
+        item.creation_time
+    }
+
+    fn matches(&self, tournament_data: &Self::Item) -> bool {
+        if let Some(ref tournament_data_ids) = self.tournament_data_id {
+            if !(tournament_data_ids.contains(&tournament_data.tournament_data_id)) {
+                return false;
+            }
+        }
+        if let Some(min_creation_time) = self.min_creation_time {
+            if tournament_data.creation_time < min_creation_time {
+                return false;
+            }
+        }
+        if let Some(max_creation_time) = self.max_creation_time {
+            if tournament_data.creation_time > max_creation_time {
+                return false;
+            }
+        }
+        if let Some(min_id) = self.min_id {
+            if tournament_data.tournament_data_id < min_id {
+                return false;
+            }
+        }
+        if let Some(max_id) = self.max_id {
+            if tournament_data.tournament_data_id > max_id {
+                return false;
+            }
+        }
+        if let Some(ref creator_user_ids) = self.creator_user_id {
+            if !(creator_user_ids.contains(&tournament_data.creator_user_id)) {
+                return false;
+            }
+        }
+        if let Some(ref tournament_ids) = self.tournament_id {
+            if !(tournament_ids.contains(&tournament_data.tournament.tournament_id)) {
+                return false;
+            }
+        }
+        if let Some(ref titles) = self.title {
+            if !(titles.contains(&tournament_data.title)) {
+                return false;
+            }
+        }
+        if let Some(active) = self.active {
+            if active != tournament_data.active {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn with_min_creation_time(self, min_creation_time: i64) -> Self {
+        request::TournamentDataViewProps {
+            min_creation_time: Some(
+                self.min_creation_time
+                    .map_or(min_creation_time, |x| x.max(min_creation_time)),
+            ),
+            ..self
+        }
+    }
 
-            // get data
-            let tournament_submission =
-                tournament_submission_service::query(&mut *client, props.clone())
-                    .await
-                    .map_err(report_postgres_err)?;
+    async fn snapshot(self, db: Db) -> Result<Vec<Self::Item>, response::AppError> {
+        let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
 
-            for u in tournament_submission.into_iter() {
-                // set next_new_id to the highest id processed + 1
-                if u.tournament_submission_id > next_new_id {
-                    next_new_id = u.tournament_submission_id + 1;
-                }
+        let props = request::TournamentDataViewProps {
+            limit: None,
+            cursor: None,
+            ..self
+        };
+
+        let tournament_data = tournament_data_service::query(con, props)
+            .await
+            .map_err(report_postgres_err)?;
 
-                let json_str =
-                    serde_json::to_string(&fill_tournament_submission(&mut *client, u).await?)
-                        .expect("serde should have serialized json");
+        let tournaments =
+            load_tournaments(con, tournament_data.iter().map(|x| x.tournament_id)).await?;
+        let resp_tournament_datas = tournament_data
+            .into_iter()
+            .map(|u| fill_tournament_data_with(&tournaments, u))
+            .collect::<Result<Vec<_>, _>>()?;
 
-                // try to send. If it fails then we close the websocket
-                if let Err(_) = websocket.send(Message::text(json_str)).await {
-                    return ();
-                }
+        Ok(resp_tournament_datas)
+    }
+}
+
+impl StreamView for request::TournamentSubmissionViewProps {
+    type Item = response::TournamentSubmission;
+
+    fn item_id(item: &Self::Item) -> i64 {
+        item.tournament_submission_id
+    }
+
+    fn item_creation_time(item: &Self::Item) -> i64 {
+        item.creation_time
+    }
+
+    fn matches(&self, tournament_submission: &Self::Item) -> bool {
+        if let Some(min_creation_time) = self.min_creation_time {
+            if tournament_submission.creation_time < min_creation_time {
+                return false;
+            }
+        }
+        if let Some(max_creation_time) = self.max_creation_time {
+            if tournament_submission.creation_time > max_creation_time {
+                return false;
             }
         }
+        if let Some(min_id) = self.min_id {
+            if tournament_submission.tournament_submission_id < min_id {
+                return false;
+            }
+        }
+        if let Some(max_id) = self.max_id {
+            if tournament_submission.tournament_submission_id > max_id {
+                return false;
+            }
+        }
+        if let Some(ref tournament_submission_ids) = self.tournament_submission_id {
+            if !(tournament_submission_ids
+                .contains(&tournament_submission.tournament_submission_id))
+            {
+                return false;
+            }
+        }
+        if let Some(ref creator_user_ids) = self.creator_user_id {
+            if !(creator_user_ids.contains(&tournament_submission.creator_user_id)) {
+                return false;
+            }
+        }
+        if let Some(ref tournament_ids) = self.tournament_id {
+            if !(tournament_ids.contains(&tournament_submission.tournament.tournament_id)) {
+                return false;
+            }
+        }
+        if let Some(ref submission_ids) = self.submission_id {
+            if !(submission_ids.contains(&tournament_submission.submission_id)) {
+                return false;
+            }
+        }
+        if let Some(ref kind) = self.kind {
+            if !(kind == &tournament_submission.kind) {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn with_min_creation_time(self, min_creation_time: i64) -> Self {
+        request::TournamentSubmissionViewProps {
+            min_creation_time: Some(
+                self.min_creation_time
+                    .map_or(min_creation_time, |x| x.max(min_creation_time)),
+            ),
+            ..self
+        }
+    }
 
-        // exclude any things that we've already seen
-        let mut props = props.clone();
-        props.min_id = Some(next_new_id);
+    async fn snapshot(self, db: Db) -> Result<Vec<Self::Item>, response::AppError> {
+        let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
 
-        // now we start streaming responses
-        while let Ok(tournament_submission) = rx.recv().await {
-            // check if the tournament_submission meets specs
-            if let Some(min_creation_time) = props.min_creation_time {
-                if !(tournament_submission.creation_time >= min_creation_time) {
-                    continue;
-                }
+        let props = request::TournamentSubmissionViewProps {
+            limit: None,
+            cursor: None,
+            ..self
+        };
+
+        let tournament_submission = tournament_submission_service::query(con, props)
+            .await
+            .map_err(report_postgres_err)?;
+
+        let tournaments =
+            load_tournaments(con, tournament_submission.iter().map(|x| x.tournament_id)).await?;
+        let reference_strategies =
+            load_reference_strategies(con, tournament_submission.iter().map(|x| x.submission_id))
+                .await?;
+        let resp_tournament_submissions = tournament_submission
+            .into_iter()
+            .map(|u| fill_tournament_submission_with(&tournaments, &reference_strategies, u))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(resp_tournament_submissions)
+    }
+}
+
+impl StreamView for request::MatchResolutionViewProps {
+    type Item = response::MatchResolutionLite;
+
+    fn item_id(item: &Self::Item) -> i64 {
+        item.match_resolution_id
+    }
+
+    fn item_creation_time(item: &Self::Item) -> i64 {
+        item.creation_time
+    }
+
+    fn matches(&self, match_resolution_lite: &Self::Item) -> bool {
+        if let Some(min_creation_time) = self.min_creation_time {
+            if match_resolution_lite.creation_time < min_creation_time {
+                return false;
             }
-            if let Some(max_creation_time) = props.max_creation_time {
-                if !(tournament_submission.creation_time <= max_creation_time) {
-                    continue;
-                }
+        }
+        if let Some(max_creation_time) = self.max_creation_time {
+            if match_resolution_lite.creation_time > max_creation_time {
+                return false;
             }
-            if let Some(min_id) = props.min_id {
-                if !(tournament_submission.tournament_submission_id >= min_id) {
-                    continue;
-                }
+        }
+        if let Some(min_id) = self.min_id {
+            if match_resolution_lite.match_resolution_id < min_id {
+                return false;
             }
-            if let Some(max_id) = props.max_id {
-                if !(tournament_submission.tournament_submission_id <= max_id) {
-                    continue;
-                }
+        }
+        if let Some(max_id) = self.max_id {
+            if match_resolution_lite.match_resolution_id > max_id {
+                return false;
             }
-            if let Some(ref tournament_submission_ids) = props.tournament_submission_id {
-                if !(tournament_submission_ids
-                    .contains(&tournament_submission.tournament_submission_id))
-                {
-                    continue;
-                }
+        }
+        if let Some(ref match_resolution_ids) = self.match_resolution_id {
+            if !(match_resolution_ids.contains(&match_resolution_lite.match_resolution_id)) {
+                return false;
             }
-            if let Some(ref creator_user_ids) = props.creator_user_id {
-                if !(creator_user_ids.contains(&tournament_submission.creator_user_id)) {
-                    continue;
-                }
+        }
+        if let Some(ref submission_ids) = self.submission_id {
+            if !(submission_ids.contains(&match_resolution_lite.submission_id)) {
+                return false;
             }
-            if let Some(ref tournament_ids) = props.tournament_id {
-                if !(tournament_ids.contains(&tournament_submission.tournament.tournament_id)) {
-                    continue;
-                }
+        }
+        if let Some(ref opponent_submission_ids) = self.opponent_submission_id {
+            if !(opponent_submission_ids.contains(&match_resolution_lite.opponent_submission_id)) {
+                return false;
             }
-            if let Some(ref submission_ids) = props.submission_id {
-                if !(submission_ids.contains(&tournament_submission.submission_id)) {
-                    continue;
-                }
+        }
+        if let Some(ref rounds) = self.round {
+            if !(rounds.contains(&match_resolution_lite.round)) {
+                return false;
             }
-            if let Some(ref kind) = props.kind {
-                if !(kind == &tournament_submission.kind) {
-                    continue;
-                }
+        }
+        if let Some(ref matchups) = self.matchup {
+            if !(matchups.contains(&match_resolution_lite.matchup)) {
+                return false;
             }
+        }
+        if self.tournament_finalization_id != match_resolution_lite.tournament_finalization_id {
+            return false;
+        }
+        true
+    }
+
+    fn with_min_creation_time(self, min_creation_time: i64) -> Self {
+        request::MatchResolutionViewProps {
+            min_creation_time: Some(
+                self.min_creation_time
+                    .map_or(min_creation_time, |x| x.max(min_creation_time)),
+            ),
+            ..self
+        }
+    }
+
+    async fn snapshot(self, db: Db) -> Result<Vec<Self::Item>, response::AppError> {
+        let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
+
+        let props = request::MatchResolutionViewProps {
+            limit: None,
+            cursor: None,
+            ..self
+        };
+
+        let match_resolution = match_resolution_service::query(con, props)
+            .await
+            .map_err(report_postgres_err)?;
+
+        let mut resp_match_resolutions = vec![];
+        for u in match_resolution.into_iter() {
+            resp_match_resolutions.push(fill_match_resolution_lite(con, u).await?);
+        }
+
+        Ok(resp_match_resolutions)
+    }
+}
+
+async fn send_stream_message<T: serde::Serialize>(
+    ws_tx: &mut SplitSink<warp::ws::WebSocket, Message>,
+    message: &response::StreamMessage<T>,
+) -> Result<(), response::AppError> {
+    let json_str = serde_json::to_string(message).expect("serde should have serialized json");
+    ws_tx
+        .send(Message::text(json_str))
+        .await
+        .map_err(|_| response::AppError::Network)
+}
+
+// sends every item in the view followed by SNAPSHOT_COMPLETE
+// returns the highest id sent
+// Ids are handed out as rows are inserted, but transactions commit in any order,
+// so a row can become visible after one with a higher id has already been sent.
+// Streams therefore catch up from a fence rather than from the last id they sent:
+// a creation time that every row yet to be committed is created at or after.
+// The items sent since the fence are remembered, so that querying from it again doesn't repeat them.
+#[derive(Default)]
+struct StreamFence {
+    // None until the first snapshot
+    fence: Option<i64>,
+    // creation time of each item sent at or after the fence, by id
+    sent: HashMap<i64, i64>,
+}
+
+impl StreamFence {
+    // whether an item still has to be sent, in which case it's remembered as sent
+    fn admit<P: StreamView>(&mut self, item: &P::Item) -> bool {
+        let creation_time = P::item_creation_time(item);
+        self.fence.is_none_or(|fence| creation_time >= fence)
+            && self.sent.insert(P::item_id(item), creation_time).is_none()
+    }
+
+    // whether so many sent items are remembered that it's time to catch up
+    fn is_full(&self) -> bool {
+        self.sent.len() > STREAM_FENCE_MAX_SENT
+    }
+
+    // queries the view from the fence, returning the items that haven't been sent yet,
+    // and moves the fence up to what was still open before the query
+    async fn catch_up<P: StreamView>(
+        &mut self,
+        db: &Db,
+        props: P,
+    ) -> Result<Vec<P::Item>, response::AppError> {
+        // taken first: anything committed after it is either in the query or newer than it
+        let fence = {
+            let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
+            activity_service::get_oldest_open_creation_time(con)
+                .await
+                .map_err(report_postgres_err)?
+        };
+
+        let props = match self.fence {
+            Some(fence) => props.with_min_creation_time(fence),
+            None => props,
+        };
+
+        let mut items = props.snapshot(db.clone()).await?;
+        items.retain(|item| self.admit::<P>(item));
+
+        self.fence = Some(fence);
+        self.sent.retain(|_, creation_time| *creation_time >= fence);
+
+        Ok(items)
+    }
+}
 
-            let json_str = serde_json::to_string(&tournament_submission)
-                .expect("serde should have serialized json");
+// sends every item in the view that hasn't been sent yet, followed by SNAPSHOT_COMPLETE
+async fn send_snapshot<P: StreamView>(
+    ws_tx: &mut SplitSink<warp::ws::WebSocket, Message>,
+    db: &Db,
+    fence: &mut StreamFence,
+    props: &P,
+) -> Result<(), response::AppError> {
+    for item in fence.catch_up(db, props.clone()).await? {
+        send_stream_message(ws_tx, &response::StreamMessage::Item(item)).await?;
+    }
+    send_stream_message::<P::Item>(ws_tx, &response::StreamMessage::SnapshotComplete).await?;
+    Ok(())
+}
+
+// The client sends the view props as the first text frame.
+// We then send a snapshot of the view, and afterwards every matching insert as it happens.
+// If we fall behind the broadcast channel, we send RESYNC and catch up from the stream's fence.
+async fn follow_stream<P: StreamView>(
+    db: Db,
+    mut rx: broadcast::Receiver<InsertEvent<P::Item>>,
+    websocket: warp::ws::WebSocket,
+) -> Result<(), response::AppError> {
+    let (mut ws_tx, mut ws_rx) = websocket.split();
+
+    let message = ws_rx
+        .next()
+        .await
+        // socket closed cleanly
+        .ok_or(response::AppError::StreamEndBeforeRequest)?
+        // websocket errored out for some reason
+        .map_err(|_| response::AppError::StreamEndBeforeRequest)?;
+
+    let message_str = message
+        .to_str()
+        // message wasn't text
+        .map_err(|_| response::AppError::StreamEndBeforeRequest)?;
 
-            // try to send. If it fails then we close the websocket
-            if let Err(_) = websocket.send(Message::text(json_str)).await {
-                return ();
+    let props =
+        serde_json::from_str::<P>(message_str).map_err(|_| response::AppError::DecodeError)?;
+
+    // first we send all existing ones
+    let mut fence = StreamFence::default();
+    send_snapshot(&mut ws_tx, &db, &mut fence, &props).await?;
+
+    let mut heartbeat = tokio::time::interval(STREAM_HEARTBEAT_INTERVAL);
+    let mut awaiting_pong = false;
+
+    // now we start streaming responses
+    loop {
+        tokio::select! {
+            item = rx.recv() => match item {
+                Ok(InsertEvent::Insert(item)) => {
+                    // skip things that we've already sent, such as in the snapshot
+                    if props.matches(&item) && fence.admit::<P>(&item) {
+                        send_stream_message(&mut ws_tx, &response::StreamMessage::Item(item)).await?;
+                    }
+                    // catching up moves the fence up, which lets the stream forget what's behind it
+                    if fence.is_full() {
+                        for item in fence.catch_up(&db, props.clone()).await? {
+                            send_stream_message(&mut ws_tx, &response::StreamMessage::Item(item)).await?;
+                        }
+                    }
+                }
+                Ok(InsertEvent::Resync) | Err(broadcast::error::RecvError::Lagged(_)) => {
+                    // we missed some items, so catch up from the db
+                    send_stream_message::<P::Item>(&mut ws_tx, &response::StreamMessage::Resync)
+                        .await?;
+                    send_snapshot(&mut ws_tx, &db, &mut fence, &props).await?;
+                }
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            message = ws_rx.next() => match message {
+                Some(Ok(message)) if message.is_close() => return Ok(()),
+                Some(Ok(message)) if message.is_pong() => awaiting_pong = false,
+                Some(Ok(_)) => {}
+                // client went away
+                _ => return Ok(()),
+            },
+            _ = heartbeat.tick() => {
+                // client never answered our last ping
+                if awaiting_pong {
+                    return Err(response::AppError::Network);
+                }
+                ws_tx
+                    .send(Message::ping(vec![]))
+                    .await
+                    .map_err(|_| response::AppError::Network)?;
+                send_stream_message::<P::Item>(&mut ws_tx, &response::StreamMessage::Heartbeat)
+                    .await?;
+                awaiting_pong = true;
             }
         }
+    }
+}
 
-        // this will only be reached if the loop encounters an error
-        // if so then close
-        return ();
-    };
+pub async fn tournament_data_stream(
+    AppData {
+        db,
+        tournament_data_insert_tx,
+        ..
+    }: AppData,
+    websocket: warp::ws::WebSocket,
+) {
+    // subscribe to sender before we take the snapshot so nothing slips through
+    let rx = tournament_data_insert_tx.subscribe();
+
+    let _ = follow_stream::<request::TournamentDataViewProps>(db, rx, websocket).await;
+}
+
+pub async fn tournament_submission_stream(
+    AppData {
+        db,
+        tournament_submission_insert_tx,
+        ..
+    }: AppData,
+    websocket: warp::ws::WebSocket,
+) {
+    // subscribe to sender before we take the snapshot so nothing slips through
+    let rx = tournament_submission_insert_tx.subscribe();
+
+    let _ = follow_stream::<request::TournamentSubmissionViewProps>(db, rx, websocket).await;
 }
 
 pub async fn match_resolution_lite_stream(
     AppData {
         db,
-        auth_service,
         match_resolution_insert_tx,
         ..
     }: AppData,
     websocket: warp::ws::WebSocket,
 ) {
-    tokio::pin!(websocket);
+    // subscribe to sender before we take the snapshot so nothing slips through
+    let rx = match_resolution_insert_tx.subscribe();
 
-    // subscribe to sender
-    let mut rx = match_resolution_insert_tx.subscribe();
+    let _ = follow_stream::<request::MatchResolutionViewProps>(db, rx, websocket).await;
+}
 
-    let result: Result<(), response::AppError> = try {
-        let message = websocket
-            .next()
-            .await
-            // socket closed cleanly
-            .ok_or(response::AppError::StreamEndBeforeRequest)?
-            // websocket errored out for some reason
-            .map_err(|_| response::AppError::StreamEndBeforeRequest)?;
+// scores everything resolved so far between the competing entries of a tournament
+async fn load_standings(
+    db: &Db,
+    props: &request::TournamentStandingsStreamProps,
+) -> Result<standings::Standings, response::AppError> {
+    let con: &mut tokio_postgres::Client = &mut *db.get().await.map_err(report_pool_err)?;
 
-        let message_str = message
-            .to_str()
-            // message wasn't text
-            .map_err(|_| response::AppError::StreamEndBeforeRequest)?;
+    let entries: Vec<standings::StandingsEntry> = match props.tournament_finalization_id {
+        // the entries frozen by the final evaluation
+        Some(tournament_finalization_id) => {
+            let tournament_finalization =
+                tournament_finalization_service::get_by_tournament_id(con, props.tournament_id)
+                    .await
+                    .map_err(report_postgres_err)?;
+            match tournament_finalization {
+                Some(x) if x.tournament_finalization_id == tournament_finalization_id => {}
+                _ => return Err(response::AppError::NotFound),
+            }
+            tournament_finalization_entry_service::get_by_tournament_finalization_id(
+                con,
+                tournament_finalization_id,
+            )
+            .await
+            .map_err(report_postgres_err)?
+            .into_iter()
+            .map(|x| standings::StandingsEntry {
+                tournament_submission_id: x.tournament_submission_id,
+                submission_id: x.submission_id,
+                name: x.name,
+                reference_strategy: None,
+            })
+            .collect()
+        }
+        // whatever is competing right now
+        None => tournament_submission_service::get_recent_by_kind(
+            con,
+            props.tournament_id,
+            &[TournamentSubmissionKind::Compete],
+        )
+        .await
+        .map_err(report_postgres_err)?
+        .into_iter()
+        .map(|x| standings::StandingsEntry {
+            tournament_submission_id: x.tournament_submission_id,
+            submission_id: x.submission_id,
+            name: x.name,
+            reference_strategy: None,
+        })
+        .collect(),
+    };
 
-        let props = serde_json::from_str::<request::MatchResolutionViewProps>(message_str)
-            .map_err(|_| response::AppError::DecodeError)?;
+    let reference_strategies =
+        load_reference_strategies(con, entries.iter().map(|x| x.submission_id)).await?;
+    let entries: Vec<standings::StandingsEntry> = entries
+        .into_iter()
+        .map(|x| standings::StandingsEntry {
+            reference_strategy: reference_strategies.get(&x.submission_id).cloned(),
+            ..x
+        })
+        .collect();
 
-        // validate that api key is valid
+    let submission_ids: Vec<i64> = entries.iter().map(|x| x.submission_id).collect();
 
-        // initialize the next novel id to the one specified
-        let mut next_new_id = props.min_id.unwrap_or(0);
+    let match_resolutions = match_resolution_service::query(
+        con,
+        request::MatchResolutionViewProps {
+            min_creation_time: None,
+            max_creation_time: None,
+            min_id: None,
+            max_id: None,
+            match_resolution_id: None,
+            submission_id: Some(submission_ids.clone()),
+            opponent_submission_id: Some(submission_ids),
+            round: None,
+            matchup: None,
+            tournament_finalization_id: props.tournament_finalization_id,
+            only_recent: true,
+            limit: None,
+            cursor: None,
+            api_key: String::new(),
+        },
+    )
+    .await
+    .map_err(report_postgres_err)?;
 
-        // first we grab all existing ones
-        {
-            let client: &mut tokio_postgres::Client =
-                &mut *db.get().await.map_err(report_pool_err)?;
+    let mut standings = standings::Standings::new(entries);
+    for match_resolution in match_resolutions {
+        standings.apply(&fill_match_resolution_lite(con, match_resolution).await?);
+    }
 
-            // get data
-            let match_resolution = match_resolution_service::query(&mut *client, props.clone())
-                .await
-                .map_err(report_postgres_err)?;
+    Ok(standings)
+}
 
-            for u in match_resolution.into_iter() {
-                // set next_new_id to the highest id processed + 1
-                if u.match_resolution_id > next_new_id {
-                    next_new_id = u.match_resolution_id + 1;
-                }
+async fn send_standings(
+    ws_tx: &mut SplitSink<warp::ws::WebSocket, Message>,
+    standings: &mut standings::Standings,
+) -> Result<(), response::AppError> {
+    for standing in standings.publish() {
+        send_stream_message(ws_tx, &response::StreamMessage::Item(standing)).await?;
+    }
+    Ok(())
+}
 
-                let json_str =
-                    serde_json::to_string(&fill_match_resolution_lite(&mut *client, u).await?)
-                        .expect("serde should have serialized json");
+// Like follow_stream, but instead of forwarding resolutions we score them as they land,
+// and only send the entries whose score or rank changed.
+async fn follow_standings(
+    db: Db,
+    mut match_resolution_rx: broadcast::Receiver<InsertEvent<response::MatchResolutionLite>>,
+    mut tournament_submission_rx: broadcast::Receiver<InsertEvent<response::TournamentSubmission>>,
+    websocket: warp::ws::WebSocket,
+) -> Result<(), response::AppError> {
+    let (mut ws_tx, mut ws_rx) = websocket.split();
 
-                // try to send. If it fails then we close the websocket
-                if let Err(_) = websocket.send(Message::text(json_str)).await {
-                    return ();
-                }
-            }
-        }
+    let message = ws_rx
+        .next()
+        .await
+        // socket closed cleanly
+        .ok_or(response::AppError::StreamEndBeforeRequest)?
+        // websocket errored out for some reason
+        .map_err(|_| response::AppError::StreamEndBeforeRequest)?;
+
+    let message_str = message
+        .to_str()
+        // message wasn't text
+        .map_err(|_| response::AppError::StreamEndBeforeRequest)?;
+
+    let props = serde_json::from_str::<request::TournamentStandingsStreamProps>(message_str)
+        .map_err(|_| response::AppError::DecodeError)?;
+
+    // first we send the whole leaderboard
+    let mut standings = load_standings(&db, &props).await?;
+    send_standings(&mut ws_tx, &mut standings).await?;
+    send_stream_message::<response::Standing>(
+        &mut ws_tx,
+        &response::StreamMessage::SnapshotComplete,
+    )
+    .await?;
 
-        // exclude any things that we've already seen
-        let mut props = props.clone();
-        props.min_id = Some(next_new_id);
+    let mut heartbeat = tokio::time::interval(STREAM_HEARTBEAT_INTERVAL);
+    let mut awaiting_pong = false;
 
-        // now we start streaming responses
-        while let Ok(match_resolution_lite) = rx.recv().await {
-            // check if the match_resolution meets specs
-            if let Some(min_creation_time) = props.min_creation_time {
-                if !(match_resolution_lite.creation_time >= min_creation_time) {
-                    continue;
-                }
-            }
-            if let Some(max_creation_time) = props.max_creation_time {
-                if !(match_resolution_lite.creation_time <= max_creation_time) {
-                    continue;
-                }
-            }
-            if let Some(min_id) = props.min_id {
-                if !(match_resolution_lite.match_resolution_id >= min_id) {
-                    continue;
-                }
-            }
-            if let Some(max_id) = props.max_id {
-                if !(match_resolution_lite.match_resolution_id <= max_id) {
-                    continue;
-                }
-            }
-            if let Some(ref match_resolution_ids) = props.match_resolution_id {
-                if !(match_resolution_ids.contains(&match_resolution_lite.match_resolution_id)) {
-                    continue;
-                }
-            }
-            if let Some(ref submission_ids) = props.submission_id {
-                if !(submission_ids.contains(&match_resolution_lite.submission_id)) {
-                    continue;
+    // now we send updates as resolutions land
+    loop {
+        tokio::select! {
+            item = match_resolution_rx.recv() => match item {
+                Ok(InsertEvent::Insert(match_resolution)) => {
+                    if match_resolution.tournament_finalization_id == props.tournament_finalization_id
+                        && standings.apply(&match_resolution)
+                    {
+                        send_standings(&mut ws_tx, &mut standings).await?;
+                    }
                 }
-            }
-            if let Some(ref opponent_submission_ids) = props.opponent_submission_id {
-                if !(opponent_submission_ids
-                    .contains(&match_resolution_lite.opponent_submission_id))
-                {
-                    continue;
+                Ok(InsertEvent::Resync) | Err(broadcast::error::RecvError::Lagged(_)) => {
+                    // we missed some resolutions, so rescore from the db
+                    send_stream_message::<response::Standing>(&mut ws_tx, &response::StreamMessage::Resync)
+                        .await?;
+                    standings = load_standings(&db, &props).await?;
+                    send_standings(&mut ws_tx, &mut standings).await?;
+                    send_stream_message::<response::Standing>(&mut ws_tx, &response::StreamMessage::SnapshotComplete)
+                        .await?;
                 }
-            }
-            if let Some(ref rounds) = props.round {
-                if !(rounds.contains(&match_resolution_lite.round)) {
-                    continue;
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            item = tournament_submission_rx.recv() => match item {
+                // the final evaluation's entries never change
+                Ok(InsertEvent::Insert(tournament_submission)) if props.tournament_finalization_id.is_some()
+                    || tournament_submission.tournament.tournament_id != props.tournament_id => {}
+                // someone entered or left the competition, so rescore with the new entries
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {
+                    standings = load_standings(&db, &props).await?.with_published(standings);
+                    send_standings(&mut ws_tx, &mut standings).await?;
                 }
-            }
-            if let Some(ref matchups) = props.matchup {
-                if !(matchups.contains(&match_resolution_lite.matchup)) {
-                    continue;
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            message = ws_rx.next() => match message {
+                Some(Ok(message)) if message.is_close() => return Ok(()),
+                Some(Ok(message)) if message.is_pong() => awaiting_pong = false,
+                Some(Ok(_)) => {}
+                // client went away
+                _ => return Ok(()),
+            },
+            _ = heartbeat.tick() => {
+                // client never answered our last ping
+                if awaiting_pong {
+                    return Err(response::AppError::Network);
                 }
+                ws_tx
+                    .send(Message::ping(vec![]))
+                    .await
+                    .map_err(|_| response::AppError::Network)?;
+                send_stream_message::<response::Standing>(&mut ws_tx, &response::StreamMessage::Heartbeat)
+                    .await?;
+                awaiting_pong = true;
             }
-            let json_str = serde_json::to_string(&match_resolution_lite)
-                .expect("serde should have serialized json");
+        }
+    }
+}
+
+pub async fn tournament_standings_stream(
+    AppData {
+        db,
+        match_resolution_insert_tx,
+        tournament_submission_insert_tx,
+        ..
+    }: AppData,
+    websocket: warp::ws::WebSocket,
+) {
+    // subscribe to senders before we load the standings so nothing slips through
+    let match_resolution_rx = match_resolution_insert_tx.subscribe();
+    let tournament_submission_rx = tournament_submission_insert_tx.subscribe();
+
+    let _ = follow_standings(db, match_resolution_rx, tournament_submission_rx, websocket).await;
+}
 
-            // try to send. If it fails then we close the websocket
-            if let Err(_) = websocket.send(Message::text(json_str)).await {
-                return ();
+pub type EventStream = Pin<Box<dyn Stream<Item = Result<warp::sse::Event, Infallible>> + Send>>;
+
+fn stream_event<T: serde::Serialize>(
+    id: Option<i64>,
+    message: &response::StreamMessage<T>,
+) -> warp::sse::Event {
+    let event = warp::sse::Event::default()
+        .json_data(message)
+        .expect("serde should have serialized json");
+    match id {
+        Some(id) => event.id(id.to_string()),
+        None => event,
+    }
+}
+
+// sends every item in the view that hasn't been sent yet, catching up from the stream's fence
+// their event ids are the fence from before, since the client is only past the new one once it has them all
+async fn send_event_items<P: StreamView>(
+    tx: &mpsc::Sender<warp::sse::Event>,
+    db: &Db,
+    fence: &mut StreamFence,
+    props: &P,
+) -> Result<(), response::AppError> {
+    let resume_from = fence.fence;
+    for item in fence.catch_up(db, props.clone()).await? {
+        tx.send(stream_event(
+            resume_from,
+            &response::StreamMessage::Item(item),
+        ))
+        .await
+        .map_err(|_| response::AppError::Network)?;
+    }
+    Ok(())
+}
+
+// like send_event_items, followed by SNAPSHOT_COMPLETE, which carries the new fence
+async fn send_event_snapshot<P: StreamView>(
+    tx: &mpsc::Sender<warp::sse::Event>,
+    db: &Db,
+    fence: &mut StreamFence,
+    props: &P,
+) -> Result<(), response::AppError> {
+    send_event_items(tx, db, fence, props).await?;
+    tx.send(stream_event::<P::Item>(
+        fence.fence,
+        &response::StreamMessage::SnapshotComplete,
+    ))
+    .await
+    .map_err(|_| response::AppError::Network)
+}
+
+// The same protocol as follow_stream, but over server-sent events.
+// Each item's id is used as its event id, so clients can resume with Last-Event-ID.
+fn follow_event_stream<P: StreamView>(
+    db: Db,
+    mut rx: broadcast::Receiver<InsertEvent<P::Item>>,
+    props: P,
+    last_event_id: Option<i64>,
+) -> EventStream {
+    let (tx, events) = mpsc::channel(64);
+
+    tokio::task::spawn(async move {
+        let _: Result<(), response::AppError> = async {
+            // resume from the fence of the last event the client saw,
+            // which may send some items again, but never skips any
+            let mut fence = StreamFence {
+                fence: last_event_id,
+                ..StreamFence::default()
+            };
+
+            // first we send all existing ones
+            send_event_snapshot(&tx, &db, &mut fence, &props).await?;
+
+            // now we start streaming responses
+            loop {
+                let item = tokio::select! {
+                    item = rx.recv() => item,
+                    // the client went away, which waiting on rx alone would only notice
+                    // once a matching item came along
+                    _ = tx.closed() => return Ok(()),
+                };
+                match item {
+                    Ok(InsertEvent::Insert(item)) => {
+                        // skip things that we've already sent, such as in the snapshot
+                        if props.matches(&item) && fence.admit::<P>(&item) {
+                            tx.send(stream_event(
+                                fence.fence,
+                                &response::StreamMessage::Item(item),
+                            ))
+                            .await
+                            .map_err(|_| response::AppError::Network)?;
+                        }
+                        // catching up moves the fence up, which lets the stream forget what's behind it
+                        if fence.is_full() {
+                            send_event_items(&tx, &db, &mut fence, &props).await?;
+                        }
+                    }
+                    Ok(InsertEvent::Resync) | Err(broadcast::error::RecvError::Lagged(_)) => {
+                        // we missed some items, so catch up from the db
+                        tx.send(stream_event::<P::Item>(
+                            None,
+                            &response::StreamMessage::Resync,
+                        ))
+                        .await
+                        .map_err(|_| response::AppError::Network)?;
+                        send_event_snapshot(&tx, &db, &mut fence, &props).await?;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return Ok(()),
+                }
             }
         }
+        .await;
+    });
 
-        // this will only be reached if the loop encounters an error
-        // if so then close
-        return ();
-    };
+    // dropping this stream closes events, which stops the task above
+    Box::pin(futures_util::stream::unfold(
+        events,
+        |mut events| async move { events.recv().await.map(|event| (Ok(event), events)) },
+    ))
+}
+
+pub async fn tournament_data_event_stream(
+    AppData {
+        db,
+        tournament_data_insert_tx,
+        ..
+    }: AppData,
+    props: request::TournamentDataViewProps,
+    last_event_id: Option<i64>,
+) -> Result<EventStream, response::AppError> {
+    // subscribe to sender before we take the snapshot so nothing slips through
+    let rx = tournament_data_insert_tx.subscribe();
+
+    Ok(follow_event_stream(db, rx, props, last_event_id))
+}
+
+pub async fn tournament_submission_event_stream(
+    AppData {
+        db,
+        tournament_submission_insert_tx,
+        ..
+    }: AppData,
+    props: request::TournamentSubmissionViewProps,
+    last_event_id: Option<i64>,
+) -> Result<EventStream, response::AppError> {
+    // subscribe to sender before we take the snapshot so nothing slips through
+    let rx = tournament_submission_insert_tx.subscribe();
+
+    Ok(follow_event_stream(db, rx, props, last_event_id))
+}
+
+pub async fn match_resolution_lite_event_stream(
+    AppData {
+        db,
+        match_resolution_insert_tx,
+        ..
+    }: AppData,
+    props: request::MatchResolutionViewProps,
+    last_event_id: Option<i64>,
+) -> Result<EventStream, response::AppError> {
+    // subscribe to sender before we take the snapshot so nothing slips through
+    let rx = match_resolution_insert_tx.subscribe();
+
+    Ok(follow_event_stream(db, rx, props, last_event_id))
 }
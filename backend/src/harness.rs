@@ -0,0 +1,139 @@
+use super::db_types::*;
+use super::utils;
+use std::collections::HashMap;
+
+// splitmix64's finalizer, which spreads every input bit over the output
+fn mix(x: u64) -> u64 {
+    let x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    let x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+// The seed for python's random in one side of a round.
+// It only depends on where the round is in its matchup, so replaying a round gives the bots the same random numbers.
+// Not on the tournament: a pair's match resolutions are shared by every tournament they're both entered in.
+// The pair is ordered, so the two sides of a round get different seeds.
+// Replays use the seed recorded with each match resolution, so changing this only changes new matches.
+pub fn match_seed(
+    matchup: i64,
+    round: i64,
+    submission_id: i64,
+    opponent_submission_id: i64,
+) -> i64 {
+    let hash = [matchup, round, submission_id, opponent_submission_id]
+        .into_iter()
+        .fold(0u64, |hash, x| {
+            mix(hash.wrapping_add(0x9e37_79b9_7f4a_7c15) ^ x as u64)
+        });
+    // 53 bits, so it's the same number in postgres, python and javascript
+    (hash >> 11) as i64
+}
+
+// The files run for one side of a round.
+// `run` seeds random, then exits with 100 if the submission defected and 101 if it cooperated.
+// Bots can read the seed from the `pdarena` module.
+// Without a seed, the files are the ones run before bots were seeded, so that old matches can be replayed.
+pub fn match_files(
+    submission: &Submission,
+    opponent_submission: &Submission,
+    opponent_defection_history: &[Option<bool>],
+    seed: Option<i64>,
+) -> HashMap<String, String> {
+    let opponent_defection_history_str = opponent_defection_history
+        .iter()
+        .map(|x| match x {
+            None => "None",
+            Some(true) => "True",
+            Some(false) => "False",
+        })
+        .collect::<Vec<&str>>()
+        .join(",");
+
+    // create random names for submission and opponent submission
+    let submission_file_name = format!("mod_{}", utils::random_string());
+    let opponent_submission_file_name = format!("mod_{}", utils::random_string());
+
+    let seed_code = match seed {
+        // seed before the submissions are imported, in case they use random at the top level
+        Some(_) => vec![
+            String::from("import random"),
+            String::from("import pdarena"),
+            String::from("random.seed(pdarena.seed)"),
+        ],
+        None => vec![],
+    };
+
+    let run_code = [
+        vec![String::from("#!/usr/bin/env python3")],
+        seed_code,
+        vec![
+            format!("import {} as Sub", submission_file_name),
+            format!("import {} as Opp", opponent_submission_file_name),
+            format!(
+                "opp_defection_history = [{}]",
+                opponent_defection_history_str
+            ),
+            String::from("defected = Sub.should_defect(Opp.should_defect, opp_defection_history)"),
+            format!("exit(100 if defected else 101)"),
+        ],
+    ]
+    .concat()
+    .join("\n");
+
+    let mut files: HashMap<String, String> = [
+        (String::from("run"), run_code),
+        (
+            format!("{}.py", submission_file_name),
+            submission.code.clone(),
+        ),
+        (
+            format!("{}.py", opponent_submission_file_name),
+            opponent_submission.code.clone(),
+        ),
+    ]
+    .into_iter()
+    .collect();
+
+    if let Some(seed) = seed {
+        files.insert(String::from("pdarena.py"), format!("seed = {}\n", seed));
+    }
+
+    files
+}
+
+// None if the bot crashed or timed out
+pub fn defected_from_exit_code(exit_code: Option<i64>) -> Option<bool> {
+    match exit_code {
+        Some(100) => Some(true),
+        Some(101) => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // seeds are recorded with each match resolution, so these must never change
+    #[test]
+    fn match_seed_is_stable() {
+        assert_eq!(match_seed(0, 0, 8, 3), 6379574265795425);
+        assert_eq!(match_seed(0, 1, 8, 3), 7000756779558961);
+        assert_eq!(match_seed(1, 4, 8, 3), 3328594047697340);
+        assert_eq!(match_seed(0, 0, 0, 1), 3713873072766851);
+    }
+
+    #[test]
+    fn match_seed_differs_between_sides() {
+        assert_ne!(match_seed(0, 0, 8, 3), match_seed(0, 0, 3, 8));
+        assert_ne!(match_seed(0, 0, 8, 8), match_seed(0, 1, 8, 8));
+    }
+
+    #[test]
+    fn match_seed_fits_in_a_double() {
+        for round in 0..100 {
+            let seed = match_seed(round % 3, round, 8, 3);
+            assert!((0..1 << 53).contains(&seed));
+        }
+    }
+}
@@ -1,7 +1,11 @@
-#![feature(try_blocks)]
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
+use std::collections::HashMap;
 use std::error::Error;
+use std::io::Write;
+use std::net::IpAddr;
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::Duration;
 use warp::Filter;
 
 use std::sync::Arc;
@@ -21,21 +25,95 @@ mod request;
 mod response;
 
 // db web stuff
+mod activity_service;
+mod match_resolution_output_service;
 mod match_resolution_service;
+mod reference_submission_service;
 mod submission_service;
 mod tournament_data_service;
+mod tournament_finalization_entry_service;
+mod tournament_finalization_service;
 mod tournament_service;
 mod tournament_submission_service;
 
+mod analysis;
 mod api;
+mod archive;
 mod db_types;
 mod handlers;
+mod harness;
+mod migrations;
+mod openapi;
+mod standings;
+mod strategies;
 
 static MAX_TIME: f32 = 1.0;
+static MAX_TEST_RUN_ROUNDS: i64 = 20;
+// owns the reference submissions, no real user has this id
+static REFERENCE_CREATOR_USER_ID: i64 = 0;
+static DEFAULT_PAGE_LIMIT: i64 = 100;
+static MAX_PAGE_LIMIT: i64 = 1000;
+static STDERR_EXCERPT_LENGTH: usize = 1000;
+static STREAM_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+// how many sent items a stream remembers before it catches up to move its fence, see handlers::StreamFence
+static STREAM_FENCE_MAX_SENT: usize = 4096;
+// results downloads hold a pooled connection until the client has read them, so only this many run at once
+static MAX_RESULTS_DOWNLOADS: usize = 4;
+static OUTPUT_PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
 static SERVICE_NAME: &str = "pdarena-service";
 
 #[derive(Parser, Clone)]
+#[clap(args_conflicts_with_subcommands = true)]
 struct Opts {
+    #[clap(subcommand)]
+    command: Option<Command>,
+    // serving is the default when no subcommand is given
+    #[clap(flatten)]
+    serve: Option<ServeOpts>,
+}
+
+#[derive(Subcommand, Clone)]
+enum Command {
+    // apply pending database migrations and exit
+    Migrate {
+        #[clap(long)]
+        database_url: String,
+    },
+    // write a tournament to an archive file, see archive.rs
+    Export {
+        #[clap(long)]
+        database_url: String,
+        #[clap(long)]
+        tournament_id: i64,
+        // replace user ids and entry names, for publishing datasets
+        #[clap(long)]
+        anonymize: bool,
+        #[clap(long)]
+        output: PathBuf,
+    },
+    // recreate a tournament from an archive file, owned by the given user
+    Import {
+        #[clap(long)]
+        database_url: String,
+        #[clap(long)]
+        creator_user_id: i64,
+        #[clap(long)]
+        input: PathBuf,
+    },
+    // rebuild the files a match was run with
+    // writes them to stdout as a tar, or runs them if given an executor
+    Replay {
+        #[clap(long)]
+        database_url: String,
+        #[clap(long)]
+        match_resolution_id: i64,
+        #[clap(long)]
+        pythonbox_service_url: Option<String>,
+    },
+}
+
+#[derive(Args, Clone)]
+struct ServeOpts {
     #[clap(long)]
     site_external_url: String,
     #[clap(long)]
@@ -48,40 +126,91 @@ struct Opts {
     port: u16,
     #[clap(long)]
     workers: u16,
+    #[clap(long, default_value_t = 30)]
+    max_submissions_per_hour: i64,
+    #[clap(long, default_value_t = 3)]
+    max_compete_per_tournament: i64,
+    #[clap(long, default_value_t = 20000)]
+    max_queued_matches_per_user: i64,
+    #[clap(long, default_value_t = 10.0)]
+    requests_per_second: f64,
+    #[clap(long, default_value_t = 50.0)]
+    request_burst: f64,
+    // reverse proxies whose X-Forwarded-For header is believed, may be given more than once
+    #[clap(long)]
+    trusted_proxy: Vec<IpAddr>,
+    // bytes of stdout and stderr kept for each match
+    #[clap(long, default_value_t = 16384)]
+    max_output_length: usize,
+    // how long the output of replayed matches and cancelled submissions is kept
+    #[clap(long, default_value_t = 24)]
+    output_retention_hours: u64,
 }
 
 pub type Db = deadpool_postgres::Pool;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct MatchupTask {
+    // which tournament the matchup is played for, since submissions can be entered in several
+    pub tournament_id: i64,
     pub matchup_num: i64,
     pub n_rounds: i64,
     pub submission_id: i64,
     pub opponent_submission_id: i64,
+    pub tournament_finalization_id: Option<i64>,
+    // the user whose queued match budget this task counts against
+    pub budget_user_id: Option<i64>,
+}
+
+impl MatchupTask {
+    // each round executes both submissions once
+    pub fn n_matches(&self) -> i64 {
+        self.n_rounds * 2
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Limits {
+    pub max_submissions_per_hour: i64,
+    pub max_compete_per_tournament: i64,
+    pub max_queued_matches_per_user: i64,
+    pub requests_per_second: f64,
+    pub request_burst: f64,
+    pub trusted_proxies: Vec<IpAddr>,
+    pub max_output_length: usize,
+}
+
+// number of matches currently queued on behalf of each user
+// a std mutex, since it's never held across an await, and has to be usable when a reservation is dropped
+pub type QueuedMatches = Arc<std::sync::Mutex<HashMap<i64, i64>>>;
+
+// what the insert listener broadcasts to stream subscribers
+#[derive(Clone, Debug)]
+pub enum InsertEvent<T> {
+    Insert(T),
+    // inserts may have been missed, so subscribers have to catch up from the db, as if they had lagged
+    Resync,
 }
 
 #[derive(Clone)]
 pub struct AppData {
     pub db: Db,
+    // permits for results downloads in progress, see MAX_RESULTS_DOWNLOADS
+    pub results_downloads: Arc<tokio::sync::Semaphore>,
     pub site_external_url: String,
-    pub match_resolution_insert_tx: broadcast::Sender<response::MatchResolutionLite>,
-    pub tournament_submission_insert_tx: broadcast::Sender<response::TournamentSubmission>,
+    pub match_resolution_insert_tx: broadcast::Sender<InsertEvent<response::MatchResolutionLite>>,
+    pub tournament_data_insert_tx: broadcast::Sender<InsertEvent<response::TournamentData>>,
+    pub tournament_submission_insert_tx:
+        broadcast::Sender<InsertEvent<response::TournamentSubmission>>,
     pub matchup_task_tx: mpsc::UnboundedSender<MatchupTask>,
+    pub queued_matches: QueuedMatches,
+    pub limits: Limits,
     pub auth_service: AuthService,
+    pub run_code_service: run_code::RunCodeService,
 }
 
-#[tokio::main]
-async fn main() -> Result<(), ()> {
-    let Opts {
-        database_url,
-        site_external_url,
-        auth_service_url,
-        pythonbox_service_url,
-        port,
-        workers,
-    } = Opts::parse();
-
-    let postgres_config = tokio_postgres::Config::from_str(&database_url).map_err(|e| {
+fn connect(database_url: &str) -> Result<(tokio_postgres::Config, Db), ()> {
+    let postgres_config = tokio_postgres::Config::from_str(database_url).map_err(|e| {
         utils::log(utils::Event {
             msg: e.to_string(),
             source: e.source().map(|x| x.to_string()),
@@ -90,7 +219,7 @@ async fn main() -> Result<(), ()> {
     })?;
 
     let mgr = deadpool_postgres::Manager::from_config(
-        postgres_config,
+        postgres_config.clone(),
         tokio_postgres::NoTls,
         deadpool_postgres::ManagerConfig {
             recycling_method: deadpool_postgres::RecyclingMethod::Fast,
@@ -108,6 +237,191 @@ async fn main() -> Result<(), ()> {
             })
         })?;
 
+    Ok((postgres_config, pool))
+}
+
+async fn migrate(pool: &Db) -> Result<(), ()> {
+    let con: &mut tokio_postgres::Client = &mut *pool.get().await.map_err(log_fatal)?;
+
+    migrations::migrate(con).await.map_err(log_fatal)?;
+    handlers::install_reference_submissions(con)
+        .await
+        .map_err(log_fatal)?;
+
+    Ok(())
+}
+
+fn log_fatal(e: impl Error) {
+    utils::log(utils::Event {
+        msg: e.to_string(),
+        source: e.source().map(|x| x.to_string()),
+        severity: utils::SeverityKind::Fatal,
+    })
+}
+
+async fn export(pool: &Db, tournament_id: i64, anonymize: bool, output: PathBuf) -> Result<(), ()> {
+    let con: &mut tokio_postgres::Client = &mut *pool.get().await.map_err(log_fatal)?;
+
+    let archive = handlers::export_tournament(con, tournament_id)
+        .await
+        .map_err(log_fatal)?;
+    let archive = if anonymize {
+        archive.anonymize()
+    } else {
+        archive
+    };
+
+    let file = std::fs::File::create(output).map_err(log_fatal)?;
+    serde_json::to_writer(std::io::BufWriter::new(file), &archive).map_err(log_fatal)?;
+
+    Ok(())
+}
+
+async fn import(pool: &Db, creator_user_id: i64, input: PathBuf) -> Result<(), ()> {
+    let con: &mut tokio_postgres::Client = &mut *pool.get().await.map_err(log_fatal)?;
+
+    let file = std::fs::File::open(input).map_err(log_fatal)?;
+    let archive: archive::Archive =
+        serde_json::from_reader(std::io::BufReader::new(file)).map_err(log_fatal)?;
+
+    let tournament = handlers::import_tournament(con, archive, creator_user_id)
+        .await
+        .map_err(log_fatal)?;
+
+    utils::log(utils::Event {
+        msg: format!("imported as tournament {}", tournament.tournament_id),
+        source: None::<String>,
+        severity: utils::SeverityKind::Info,
+    });
+
+    Ok(())
+}
+
+async fn replay(
+    pool: &Db,
+    match_resolution_id: i64,
+    pythonbox_service_url: Option<String>,
+) -> Result<(), ()> {
+    let con: &mut tokio_postgres::Client = &mut *pool.get().await.map_err(log_fatal)?;
+
+    let (match_resolution, files) = handlers::match_resolution_files(con, match_resolution_id)
+        .await
+        .map_err(log_fatal)?;
+
+    let pythonbox_service_url = match pythonbox_service_url {
+        Some(pythonbox_service_url) => pythonbox_service_url,
+        None => {
+            let tar_buf = run_code::tar_files(files).map_err(log_fatal)?;
+            std::io::stdout().write_all(&tar_buf).map_err(log_fatal)?;
+            return Ok(());
+        }
+    };
+
+    let run_code_service = run_code::RunCodeService::new(&pythonbox_service_url).await;
+    let resp = run_code_service
+        .send_multifile_submission(files)
+        .await
+        .map_err(log_fatal)?;
+
+    print!("{}", resp.stdout);
+    eprint!("{}", resp.stderr);
+
+    let defected = harness::defected_from_exit_code(resp.exit_code);
+    utils::log(utils::Event {
+        msg: format!(
+            "exit code {:?}: defected {:?}, originally {:?}",
+            resp.exit_code, defected, match_resolution.defected
+        ),
+        source: None::<String>,
+        severity: if defected == match_resolution.defected {
+            utils::SeverityKind::Info
+        } else {
+            utils::SeverityKind::Warning
+        },
+    });
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), ()> {
+    match Opts::parse() {
+        Opts {
+            command: Some(Command::Migrate { database_url }),
+            ..
+        } => {
+            let (_, pool) = connect(&database_url)?;
+            migrate(&pool).await
+        }
+        Opts {
+            command:
+                Some(Command::Export {
+                    database_url,
+                    tournament_id,
+                    anonymize,
+                    output,
+                }),
+            ..
+        } => {
+            let (_, pool) = connect(&database_url)?;
+            export(&pool, tournament_id, anonymize, output).await
+        }
+        Opts {
+            command:
+                Some(Command::Import {
+                    database_url,
+                    creator_user_id,
+                    input,
+                }),
+            ..
+        } => {
+            let (_, pool) = connect(&database_url)?;
+            import(&pool, creator_user_id, input).await
+        }
+        Opts {
+            command:
+                Some(Command::Replay {
+                    database_url,
+                    match_resolution_id,
+                    pythonbox_service_url,
+                }),
+            ..
+        } => {
+            let (_, pool) = connect(&database_url)?;
+            replay(&pool, match_resolution_id, pythonbox_service_url).await
+        }
+        Opts {
+            serve: Some(serve_opts),
+            ..
+        } => serve(serve_opts).await,
+        // clap requires the serve options when there's no subcommand
+        Opts { serve: None, .. } => unreachable!(),
+    }
+}
+
+async fn serve(
+    ServeOpts {
+        database_url,
+        site_external_url,
+        auth_service_url,
+        pythonbox_service_url,
+        port,
+        workers,
+        max_submissions_per_hour,
+        max_compete_per_tournament,
+        max_queued_matches_per_user,
+        requests_per_second,
+        request_burst,
+        trusted_proxy,
+        max_output_length,
+        output_retention_hours,
+    }: ServeOpts,
+) -> Result<(), ()> {
+    let (postgres_config, pool) = connect(&database_url)?;
+
+    // bring the schema up to date before anything touches it
+    migrate(&pool).await?;
+
     // open connection to auth service
     let auth_service = AuthService::new(&auth_service_url);
 
@@ -125,6 +439,7 @@ async fn main() -> Result<(), ()> {
 
     // its ok if it lags because we do the whole query all over again
     let (match_resolution_insert_tx, _) = broadcast::channel(1000);
+    let (tournament_data_insert_tx, _) = broadcast::channel(1000);
     let (tournament_submission_insert_tx, _) = broadcast::channel(1000);
 
     // submit queue
@@ -134,29 +449,64 @@ async fn main() -> Result<(), ()> {
     // vector of currently processing tasks
     let ongoing_tasks = Arc::new(Mutex::new(vec![]));
 
+    // matches queued per user
+    let queued_matches = Arc::new(std::sync::Mutex::new(HashMap::new()));
+
+    let limits = Limits {
+        max_submissions_per_hour,
+        max_compete_per_tournament,
+        max_queued_matches_per_user,
+        requests_per_second,
+        request_burst,
+        trusted_proxies: trusted_proxy,
+        max_output_length,
+    };
+
     // start workers
     for _ in 0..workers {
         tokio::task::spawn(handlers::matchup_runner(
             pool.clone(),
             run_code_service.clone(),
+            matchup_task_tx.clone(),
             matchup_task_rx.clone(),
-            match_resolution_insert_tx.clone(),
             ongoing_tasks.clone(),
+            queued_matches.clone(),
+            limits.clone(),
         ));
     }
 
+    // feed inserts made by any instance into our broadcast channels
+    tokio::task::spawn(handlers::insert_listener(
+        postgres_config,
+        pool.clone(),
+        match_resolution_insert_tx.clone(),
+        tournament_data_insert_tx.clone(),
+        tournament_submission_insert_tx.clone(),
+    ));
+
+    // delete match output nobody needs anymore
+    tokio::task::spawn(handlers::output_pruner(
+        pool.clone(),
+        Duration::from_secs(output_retention_hours * 60 * 60),
+    ));
+
     let data = AppData {
         site_external_url,
         db: pool,
+        results_downloads: Arc::new(tokio::sync::Semaphore::new(MAX_RESULTS_DOWNLOADS)),
         match_resolution_insert_tx,
+        tournament_data_insert_tx,
         tournament_submission_insert_tx,
         matchup_task_tx,
+        queued_matches,
+        limits,
         auth_service,
+        run_code_service,
     };
 
     let api = api::api(data);
 
     warp::serve(api.with(log)).run(([0, 0, 0, 0], port)).await;
 
-    return Ok(());
+    Ok(())
 }
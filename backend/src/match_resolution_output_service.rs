@@ -0,0 +1,110 @@
+use super::db_types::*;
+use crate::request::TournamentSubmissionKind;
+use std::convert::From;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for MatchResolutionOutput {
+    // select * from match_resolution_output order only, otherwise it will fail
+    fn from(row: tokio_postgres::Row) -> MatchResolutionOutput {
+        MatchResolutionOutput {
+            match_resolution_id: row.get("match_resolution_id"),
+            stdout: row.get("stdout"),
+            stdout_truncated: row.get("stdout_truncated"),
+            stderr: row.get("stderr"),
+            stderr_truncated: row.get("stderr_truncated"),
+        }
+    }
+}
+
+pub async fn add(
+    con: &mut impl GenericClient,
+    match_resolution_id: i64,
+    stdout: String,
+    stdout_truncated: bool,
+    stderr: String,
+    stderr_truncated: bool,
+) -> Result<MatchResolutionOutput, tokio_postgres::Error> {
+    con.execute(
+        "INSERT INTO
+         match_resolution_output(
+             match_resolution_id,
+             stdout,
+             stdout_truncated,
+             stderr,
+             stderr_truncated
+         )
+         VALUES ($1, $2, $3, $4, $5)
+        ",
+        &[
+            &match_resolution_id,
+            &stdout,
+            &stdout_truncated,
+            &stderr,
+            &stderr_truncated,
+        ],
+    )
+    .await?;
+
+    Ok(MatchResolutionOutput {
+        match_resolution_id,
+        stdout,
+        stdout_truncated,
+        stderr,
+        stderr_truncated,
+    })
+}
+
+pub async fn get_by_match_resolution_id(
+    con: &mut impl GenericClient,
+    match_resolution_id: i64,
+) -> Result<Option<MatchResolutionOutput>, tokio_postgres::Error> {
+    let result = con
+        .query_opt(
+            "SELECT * FROM match_resolution_output WHERE match_resolution_id=$1",
+            &[&match_resolution_id],
+        )
+        .await?
+        .map(|x| x.into());
+    Ok(result)
+}
+
+// deletes the practice output created before min_creation_time that nobody will look at again:
+// output of rounds that have since been replayed,
+// and output of submissions that have been cancelled in every tournament they entered
+// output of final evaluations is always kept
+// returns the number of outputs deleted
+pub async fn prune(
+    con: &mut impl GenericClient,
+    min_creation_time: i64,
+) -> Result<u64, tokio_postgres::Error> {
+    let sql = [
+        "DELETE FROM match_resolution_output mro",
+        " USING match_resolution mr",
+        " WHERE mro.match_resolution_id = mr.match_resolution_id",
+        " AND mr.tournament_finalization_id IS NULL",
+        " AND mr.creation_time < $1",
+        " AND (",
+        "   NOT EXISTS (",
+        "     SELECT 1 FROM current_match_resolution cmr",
+        "     WHERE cmr.match_resolution_id = mr.match_resolution_id",
+        "   )",
+        "   OR NOT EXISTS (",
+        "     SELECT 1 FROM recent_tournament_submission ts",
+        "     WHERE ts.submission_id = mr.submission_id",
+        "     AND ts.kind <> $2",
+        "   )",
+        " )",
+    ]
+    .join("\n");
+
+    let stmnt = con.prepare(&sql).await?;
+
+    con.execute(
+        &stmnt,
+        &[
+            &min_creation_time,
+            &(TournamentSubmissionKind::Cancel as i64),
+        ],
+    )
+    .await
+}
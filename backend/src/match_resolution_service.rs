@@ -1,5 +1,9 @@
 use super::db_types::*;
 use std::convert::From;
+use super::utils;
+use futures_util::{Stream, StreamExt};
+use std::pin::Pin;
+use tokio_postgres::types::ToSql;
 use tokio_postgres::GenericClient;
 
 impl From<tokio_postgres::row::Row> for MatchResolution {
@@ -11,23 +15,24 @@ impl From<tokio_postgres::row::Row> for MatchResolution {
             opponent_submission_id: row.get("opponent_submission_id"),
             round: row.get("round"),
             matchup: row.get("matchup"),
+            tournament_finalization_id: row.get("tournament_finalization_id"),
             creation_time: row.get("creation_time"),
             defected: row.get("defected"),
-            stdout: row.get("stdout"),
-            stderr: row.get("stderr"),
+            seed: row.get("seed"),
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn add(
     con: &mut impl GenericClient,
     submission_id: i64,
     opponent_submission_id: i64,
     round: i64,
     matchup: i64,
+    tournament_finalization_id: Option<i64>,
     defected: Option<bool>,
-    stdout: String,
-    stderr: String,
+    seed: Option<i64>,
 ) -> Result<MatchResolution, tokio_postgres::Error> {
     let row = con
         .query_one(
@@ -37,9 +42,9 @@ pub async fn add(
                  opponent_submission_id,
                  round,
                  matchup,
+                 tournament_finalization_id,
                  defected,
-                 stdout,
-                 stderr
+                 seed
              )
              VALUES ($1, $2, $3, $4, $5, $6, $7)
              RETURNING match_resolution_id, creation_time
@@ -49,9 +54,9 @@ pub async fn add(
                 &opponent_submission_id,
                 &round,
                 &matchup,
+                &tournament_finalization_id,
                 &defected,
-                &stdout,
-                &stderr,
+                &seed,
             ],
         )
         .await?;
@@ -63,29 +68,47 @@ pub async fn add(
         opponent_submission_id,
         round,
         matchup,
+        tournament_finalization_id,
         defected,
-        stdout,
-        stderr,
+        seed,
     })
 }
 
-pub async fn get_recent_by_submission(
+pub async fn get_by_match_resolution_id(
     con: &mut impl GenericClient,
-    submission_id: i64,
-    opponent_submission_id: i64,
+    match_resolution_id: i64,
+) -> Result<Option<MatchResolution>, tokio_postgres::Error> {
+    let result = con
+        .query_opt(
+            "SELECT * FROM match_resolution WHERE match_resolution_id=$1",
+            &[&match_resolution_id],
+        )
+        .await?
+        .map(|x| x.into());
+    Ok(result)
+}
+
+// every resolution between the given submissions, oldest first:
+// practice play, and the given final evaluation if there is one
+pub async fn get_by_submission_ids(
+    con: &mut impl GenericClient,
+    submission_ids: &[i64],
+    tournament_finalization_id: Option<i64>,
 ) -> Result<Vec<MatchResolution>, tokio_postgres::Error> {
     let sql = [
-        "SELECT mr.* FROM recent_match_resolution mr",
+        "SELECT mr.* FROM match_resolution mr",
         "WHERE 1 = 1",
-        "AND mr.submission_id = $1",
-        "AND mr.opponent_submission_id = $2",
+        "AND mr.submission_id = ANY($1)",
+        "AND mr.opponent_submission_id = ANY($1)",
+        "AND (mr.tournament_finalization_id IS NULL OR mr.tournament_finalization_id = $2)",
+        "ORDER BY mr.match_resolution_id",
     ]
     .join("\n");
 
     let stmnt = con.prepare(&sql).await?;
 
     let results = con
-        .query(&stmnt, &[&submission_id, &opponent_submission_id])
+        .query(&stmnt, &[&submission_ids, &tournament_finalization_id])
         .await?
         .into_iter()
         .map(|row| row.into())
@@ -94,79 +117,152 @@ pub async fn get_recent_by_submission(
     Ok(results)
 }
 
-// returns the last valid submission with the given submission ids and round
-pub async fn get_recent_valid_by_submission_round(
+// inserts a copy of an archived match resolution under a new id, keeping its creation time
+pub async fn restore(
+    con: &mut impl GenericClient,
+    match_resolution: MatchResolution,
+) -> Result<MatchResolution, tokio_postgres::Error> {
+    let row = con
+        .query_one(
+            "INSERT INTO
+             match_resolution(
+                 creation_time,
+                 submission_id,
+                 opponent_submission_id,
+                 round,
+                 matchup,
+                 tournament_finalization_id,
+                 defected,
+                 seed
+             )
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+             RETURNING match_resolution_id
+            ",
+            &[
+                &match_resolution.creation_time,
+                &match_resolution.submission_id,
+                &match_resolution.opponent_submission_id,
+                &match_resolution.round,
+                &match_resolution.matchup,
+                &match_resolution.tournament_finalization_id,
+                &match_resolution.defected,
+                &match_resolution.seed,
+            ],
+        )
+        .await?;
+
+    Ok(MatchResolution {
+        match_resolution_id: row.get(0),
+        ..match_resolution
+    })
+}
+
+// the most recent resolution of each round, see current_match_resolution in migrations/
+static RECENT_MATCH_RESOLUTION: &str = "SELECT mr.* FROM current_match_resolution cmr
+     INNER JOIN match_resolution mr ON mr.match_resolution_id = cmr.match_resolution_id";
+
+pub async fn get_recent_by_submission(
     con: &mut impl GenericClient,
     submission_id: i64,
     opponent_submission_id: i64,
-    round: i64,
-) -> Result<Option<MatchResolution>, tokio_postgres::Error> {
+) -> Result<Vec<MatchResolution>, tokio_postgres::Error> {
     let sql = [
-        "SELECT mr.* FROM recent_match_resolution mr",
+        RECENT_MATCH_RESOLUTION,
         "WHERE 1 = 1",
-        "AND mr.submission_id = $1",
-        "AND mr.opponent_submission_id = $2",
-        "AND mr.round = $3",
-        "AND mr.defected IS NOT NULL",
+        "AND cmr.submission_id = $1",
+        "AND cmr.opponent_submission_id = $2",
+        "AND cmr.tournament_finalization_id IS NULL",
     ]
     .join("\n");
 
     let stmnt = con.prepare(&sql).await?;
 
     let results = con
-        .query_opt(&stmnt, &[&submission_id, &opponent_submission_id, &round])
+        .query(&stmnt, &[&submission_id, &opponent_submission_id])
         .await?
-        .map(|row| row.into());
+        .into_iter()
+        .map(|row| row.into())
+        .collect();
 
     Ok(results)
 }
 
-pub async fn get_last_successful_match_round(
+pub async fn get_defection_history(
     con: &mut impl GenericClient,
     submission_id: i64,
     opponent_submission_id: i64,
-) -> Result<Option<i64>, tokio_postgres::Error> {
+    matchup: i64,
+    tournament_finalization_id: Option<i64>,
+) -> Result<Vec<MatchResolution>, tokio_postgres::Error> {
     let sql = [
-        "SELECT MAX(mr.round)",
-        "FROM recent_match_resolution mr",
+        RECENT_MATCH_RESOLUTION,
         "WHERE 1 = 1",
-        "AND mr.submission_id = $1",
-        "AND mr.opponent_submission_id = $2",
-        "AND mr.defected IS NOT NULL",
+        "AND cmr.submission_id = $1",
+        "AND cmr.opponent_submission_id = $2",
+        "AND cmr.matchup = $3",
+        "AND coalesce(cmr.tournament_finalization_id, -1) = coalesce($4::bigint, -1)",
+        "ORDER BY cmr.round",
     ]
     .join("\n");
 
     let stmnt = con.prepare(&sql).await?;
 
     let results = con
-        .query_opt(&stmnt, &[&submission_id, &opponent_submission_id])
+        .query(
+            &stmnt,
+            &[
+                &submission_id,
+                &opponent_submission_id,
+                &matchup,
+                &tournament_finalization_id,
+            ],
+        )
         .await?
-        .map(|row| row.get(0));
+        .into_iter()
+        .map(|row| row.into())
+        .collect();
 
     Ok(results)
 }
 
-pub async fn get_defection_history(
+// The history a round was played with: the latest resolution of each earlier round of the matchup
+// that already existed when the given match resolution was created.
+pub async fn get_defection_history_before(
     con: &mut impl GenericClient,
     submission_id: i64,
     opponent_submission_id: i64,
     matchup: i64,
+    tournament_finalization_id: Option<i64>,
+    round: i64,
+    match_resolution_id: i64,
 ) -> Result<Vec<MatchResolution>, tokio_postgres::Error> {
     let sql = [
-        "SELECT mr.*",
-        "FROM recent_match_resolution mr",
+        "SELECT DISTINCT ON (mr.round) mr.* FROM match_resolution mr",
         "WHERE 1 = 1",
         "AND mr.submission_id = $1",
         "AND mr.opponent_submission_id = $2",
         "AND mr.matchup = $3",
-        "ORDER BY mr.round",
+        "AND mr.tournament_finalization_id IS NOT DISTINCT FROM $4::bigint",
+        "AND mr.round < $5",
+        "AND mr.match_resolution_id < $6",
+        "ORDER BY mr.round, mr.match_resolution_id DESC",
     ]
     .join("\n");
 
     let stmnt = con.prepare(&sql).await?;
 
     let results = con
-        .query(&stmnt, &[&submission_id, &opponent_submission_id, &matchup])
+        .query(
+            &stmnt,
+            &[
+                &submission_id,
+                &opponent_submission_id,
+                &matchup,
+                &tournament_finalization_id,
+                &round,
+                &match_resolution_id,
+            ],
+        )
         .await?
         .into_iter()
         .map(|row| row.into())
@@ -175,13 +271,81 @@ pub async fn get_defection_history(
     Ok(results)
 }
 
+impl From<tokio_postgres::row::Row> for MatchRound {
+    // select from stream_rounds only, otherwise it will fail
+    fn from(row: tokio_postgres::Row) -> MatchRound {
+        MatchRound {
+            submission_id: row.get("submission_id"),
+            opponent_submission_id: row.get("opponent_submission_id"),
+            matchup: row.get("matchup"),
+            round: row.get("round"),
+            tournament_finalization_id: row.get("tournament_finalization_id"),
+            defected: row.get("defected"),
+            opponent_defected: row.get("opponent_defected"),
+        }
+    }
+}
+
+pub type MatchRoundStream =
+    Pin<Box<dyn Stream<Item = Result<MatchRound, tokio_postgres::Error>> + Send>>;
+
+// The most recent resolution of every round between the given submissions, paired with the opponent's.
+// Practice play if tournament_finalization_id is None, otherwise the given final evaluation.
+// Each round appears once, from the side of the submission with the lower id,
+// unless only the other side has been resolved so far.
+// Rows are streamed as postgres sends them, ordered by matchup and round.
+pub async fn stream_rounds(
+    con: &mut impl GenericClient,
+    submission_ids: &[i64],
+    tournament_finalization_id: Option<i64>,
+) -> Result<MatchRoundStream, tokio_postgres::Error> {
+    let sql = [
+        "SELECT",
+        "  cmr.submission_id,",
+        "  cmr.opponent_submission_id,",
+        "  cmr.matchup,",
+        "  cmr.round,",
+        "  cmr.tournament_finalization_id,",
+        "  mr.defected,",
+        "  omr.defected AS opponent_defected",
+        "FROM current_match_resolution cmr",
+        "INNER JOIN match_resolution mr ON mr.match_resolution_id = cmr.match_resolution_id",
+        "LEFT JOIN current_match_resolution ocmr",
+        "  ON ocmr.submission_id = cmr.opponent_submission_id",
+        "  AND ocmr.opponent_submission_id = cmr.submission_id",
+        "  AND ocmr.matchup = cmr.matchup",
+        "  AND ocmr.round = cmr.round",
+        "  AND coalesce(ocmr.tournament_finalization_id, -1) = coalesce(cmr.tournament_finalization_id, -1)",
+        "LEFT JOIN match_resolution omr ON omr.match_resolution_id = ocmr.match_resolution_id",
+        "WHERE 1 = 1",
+        "AND cmr.submission_id = ANY($1)",
+        "AND cmr.opponent_submission_id = ANY($1)",
+        "AND coalesce(cmr.tournament_finalization_id, -1) = coalesce($2::bigint, -1)",
+        "AND (cmr.submission_id <= cmr.opponent_submission_id OR ocmr.match_resolution_id IS NULL)",
+        "ORDER BY",
+        "  least(cmr.submission_id, cmr.opponent_submission_id),",
+        "  greatest(cmr.submission_id, cmr.opponent_submission_id),",
+        "  cmr.matchup,",
+        "  cmr.round",
+    ]
+    .join("\n");
+
+    let stmnt = con.prepare(&sql).await?;
+
+    let params: [&(dyn ToSql + Sync); 2] = [&submission_ids, &tournament_finalization_id];
+
+    let rows = con.query_raw(&stmnt, params).await?;
+
+    Ok(Box::pin(rows.map(|row| row.map(|row| row.into()))))
+}
+
 pub async fn query(
     con: &mut impl GenericClient,
     props: super::request::MatchResolutionViewProps,
 ) -> Result<Vec<MatchResolution>, tokio_postgres::Error> {
     let sql = [
         if props.only_recent {
-            "SELECT mr.* FROM recent_match_resolution mr"
+            RECENT_MATCH_RESOLUTION
         } else {
             "SELECT mr.* FROM match_resolution mr"
         },
@@ -195,7 +359,12 @@ pub async fn query(
         "AND ($7::bigint[] IS NULL OR mr.opponent_submission_id = ANY($7))",
         "AND ($8::bigint[] IS NULL OR mr.round = ANY($8))",
         "AND ($9::bigint[] IS NULL OR mr.matchup = ANY($9))",
+        // practice results if unspecified, otherwise the given final evaluation
+        "AND mr.tournament_finalization_id IS NOT DISTINCT FROM $10::bigint",
+        // start after the cursor
+        "AND ($11::bigint   IS NULL OR mr.match_resolution_id > $11)",
         "ORDER BY mr.match_resolution_id",
+        "LIMIT $12",
     ]
     .join("\n");
 
@@ -214,6 +383,9 @@ pub async fn query(
                 &props.opponent_submission_id,
                 &props.round,
                 &props.matchup,
+                &props.tournament_finalization_id,
+                &props.cursor.as_deref().and_then(utils::decode_cursor),
+                &props.limit,
             ],
         )
         .await?
@@ -0,0 +1,163 @@
+use super::utils;
+use tokio_postgres::GenericClient;
+
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+// Every change to the schema is a new file in migrations/, appended here.
+// Never edit a migration once it has been deployed: its version is already recorded as applied.
+pub static MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial",
+        sql: include_str!("../migrations/0001_initial.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "tournament_finalization",
+        sql: include_str!("../migrations/0002_tournament_finalization.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "notify_insert",
+        sql: include_str!("../migrations/0003_notify_insert.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "tournament_submission_auto_compete",
+        sql: include_str!("../migrations/0004_tournament_submission_auto_compete.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "current_match_resolution",
+        sql: include_str!("../migrations/0005_current_match_resolution.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "match_resolution_output",
+        sql: include_str!("../migrations/0006_match_resolution_output.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "match_resolution_seed",
+        sql: include_str!("../migrations/0007_match_resolution_seed.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "reference_submission",
+        sql: include_str!("../migrations/0008_reference_submission.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "tournament_data_disallowed_imports",
+        sql: include_str!("../migrations/0009_tournament_data_disallowed_imports.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "creation_time_idx",
+        sql: include_str!("../migrations/0010_creation_time_idx.sql"),
+    },
+];
+
+// held while migrating, so instances booting at the same time don't apply a migration twice
+static MIGRATION_LOCK_KEY: i64 = 0x0070_6461_7265_6e61;
+
+async fn create_schema_migration(
+    con: &mut impl GenericClient,
+) -> Result<(), tokio_postgres::Error> {
+    con.batch_execute(
+        "CREATE TABLE IF NOT EXISTS schema_migration(
+             version bigint primary key,
+             creation_time bigint not null default extract(epoch from now()) * 1000,
+             name text not null
+         )",
+    )
+    .await
+}
+
+async fn get_current_version(
+    con: &mut impl GenericClient,
+) -> Result<Option<i64>, tokio_postgres::Error> {
+    let row = con
+        .query_one("SELECT MAX(version) FROM schema_migration", &[])
+        .await?;
+    Ok(row.get(0))
+}
+
+async fn add(
+    con: &mut impl GenericClient,
+    migration: &Migration,
+) -> Result<(), tokio_postgres::Error> {
+    con.execute(
+        "INSERT INTO schema_migration(version, name) VALUES ($1, $2)",
+        &[&migration.version, &migration.name],
+    )
+    .await?;
+    Ok(())
+}
+
+// databases created before migrations existed already have the initial schema,
+// the one sql/1-pdarena.sql used to create, but nothing recording it
+async fn has_unversioned_schema(
+    con: &mut impl GenericClient,
+) -> Result<bool, tokio_postgres::Error> {
+    let row = con
+        .query_one(
+            "SELECT to_regclass('public.tournament') IS NOT NULL
+             AND NOT EXISTS (SELECT 1 FROM schema_migration)",
+            &[],
+        )
+        .await?;
+    Ok(row.get(0))
+}
+
+// applies every migration newer than the database's version, each in its own transaction
+// returns the versions that were applied
+pub async fn migrate(con: &mut tokio_postgres::Client) -> Result<Vec<i64>, tokio_postgres::Error> {
+    let mut applied = vec![];
+
+    for migration in MIGRATIONS {
+        let mut sp = con.transaction().await?;
+
+        sp.execute("SELECT pg_advisory_xact_lock($1)", &[&MIGRATION_LOCK_KEY])
+            .await?;
+
+        create_schema_migration(&mut sp).await?;
+
+        if migration.version == 1 && has_unversioned_schema(&mut sp).await? {
+            add(&mut sp, migration).await?;
+            sp.commit().await?;
+            utils::log(utils::Event {
+                msg: "recorded existing schema as the initial migration".to_owned(),
+                source: None::<String>,
+                severity: utils::SeverityKind::Info,
+            });
+            continue;
+        }
+
+        // another instance may have gotten here first
+        if get_current_version(&mut sp).await?.unwrap_or(0) >= migration.version {
+            continue;
+        }
+
+        sp.batch_execute(migration.sql).await?;
+        add(&mut sp, migration).await?;
+        sp.commit().await?;
+
+        utils::log(utils::Event {
+            msg: format!(
+                "applied migration {}: {}",
+                migration.version, migration.name
+            ),
+            source: None::<String>,
+            severity: utils::SeverityKind::Info,
+        });
+
+        applied.push(migration.version);
+    }
+
+    Ok(applied)
+}
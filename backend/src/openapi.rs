@@ -0,0 +1,256 @@
+use super::handlers;
+use super::response;
+use super::response::AppError;
+use super::AppData;
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde_json::{json, Map, Value};
+use std::future::Future;
+
+// Builds an OpenAPI 3 document describing the routes in api.rs.
+// Request and response schemas are inferred from the handler signatures,
+// so the document can't drift from request.rs and response.rs.
+pub struct OpenApi {
+    gen: SchemaGenerator,
+    paths: Map<String, Value>,
+}
+
+fn to_value(schema: Schema) -> Value {
+    serde_json::to_value(schema).expect("serde should have serialized schema")
+}
+
+fn json_content(schema: Value) -> Value {
+    json!({ "application/json": { "schema": schema } })
+}
+
+impl OpenApi {
+    pub fn new() -> OpenApi {
+        OpenApi {
+            gen: SchemaSettings::openapi3().into_generator(),
+            paths: Map::new(),
+        }
+    }
+
+    fn schema_for<T: JsonSchema>(&mut self) -> Value {
+        to_value(self.gen.subschema_for::<T>())
+    }
+
+    fn error_response(&mut self) -> Value {
+        json!({
+            "description": "the request failed",
+            "content": json_content(self.schema_for::<response::ErrorResponse>()),
+        })
+    }
+
+    // every top level field of the props becomes a query parameter
+    // the api key is excluded, since it's passed in the Authorization header instead
+    fn query_parameters<P: JsonSchema>(&mut self) -> Vec<Value> {
+        let root = self.gen.root_schema_for::<P>();
+        let object = match root.schema.object {
+            Some(object) => object,
+            None => return vec![],
+        };
+        object
+            .properties
+            .into_iter()
+            .filter(|(name, _)| name != "apiKey")
+            .map(|(name, schema)| {
+                json!({
+                    "name": name,
+                    "in": "query",
+                    "required": object.required.contains(&name),
+                    // lists are comma separated
+                    "explode": false,
+                    "schema": to_value(schema),
+                })
+            })
+            .collect()
+    }
+
+    // every {param} in the path is an integer id
+    fn path_parameters(path: &str) -> Vec<Value> {
+        path.split('/')
+            .filter_map(|x| x.strip_prefix('{').and_then(|x| x.strip_suffix('}')))
+            .map(|name| {
+                json!({
+                    "name": name,
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "integer", "format": "int64" },
+                })
+            })
+            .collect()
+    }
+
+    fn add(&mut self, path: &str, method: &str, operation: Value) {
+        self.paths
+            .entry(path.to_owned())
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .expect("path item should be an object")
+            .insert(method.to_owned(), operation);
+    }
+
+    // a route served by api::adapter
+    pub fn post<P, R, E, F>(mut self, path: &str, _handler: fn(AppData, P) -> F) -> OpenApi
+    where
+        P: JsonSchema,
+        R: JsonSchema,
+        E: Into<response::ErrorResponse>,
+        F: Future<Output = Result<R, E>>,
+    {
+        let operation = json!({
+            "requestBody": {
+                "required": true,
+                "content": json_content(self.schema_for::<P>()),
+            },
+            "responses": {
+                "200": {
+                    "description": "success",
+                    "content": json_content(self.schema_for::<R>()),
+                },
+                "default": self.error_response(),
+            },
+        });
+        self.add(path, "post", operation);
+        self
+    }
+
+    // a route served by api::get_adapter
+    pub fn get<T, P, R, F>(
+        mut self,
+        path: &str,
+        _handler: fn(AppData, T, P, String) -> F,
+    ) -> OpenApi
+    where
+        P: JsonSchema,
+        R: JsonSchema,
+        F: Future<Output = Result<R, AppError>>,
+    {
+        let mut parameters = OpenApi::path_parameters(path);
+        parameters.extend(self.query_parameters::<P>());
+        let operation = json!({
+            "parameters": parameters,
+            // anonymous requests are allowed for public views
+            "security": [{ "bearer": [] }, {}],
+            "responses": {
+                "200": {
+                    "description": "success",
+                    "content": json_content(self.schema_for::<R>()),
+                },
+                "default": self.error_response(),
+            },
+        });
+        self.add(path, "get", operation);
+        self
+    }
+
+    // a route served by api::download_adapter
+    pub fn download<T, P, F>(
+        mut self,
+        path: &str,
+        _handler: fn(AppData, T, P, String) -> F,
+    ) -> OpenApi
+    where
+        P: JsonSchema,
+        F: Future<Output = Result<handlers::ResultsDownload, AppError>>,
+    {
+        let mut parameters = OpenApi::path_parameters(path);
+        parameters.extend(self.query_parameters::<P>());
+        let row = self.schema_for::<response::MatchResult>();
+        let operation = json!({
+            "description": "A file with one MatchResult per line. CSV files start with a header row naming the fields.",
+            "parameters": parameters,
+            "security": [{ "bearer": [] }, {}],
+            "responses": {
+                "200": {
+                    "description": "a file download",
+                    "content": {
+                        "text/csv": { "schema": { "type": "string" } },
+                        "application/x-ndjson": { "schema": row },
+                    },
+                },
+                "default": self.error_response(),
+            },
+        });
+        self.add(path, "get", operation);
+        self
+    }
+
+    // a route served by api::sse_adapter
+    pub fn sse<P, F>(mut self, path: &str, _handler: fn(AppData, P, Option<i64>) -> F) -> OpenApi
+    where
+        P: handlers::StreamView + JsonSchema,
+        P::Item: JsonSchema,
+    {
+        let mut parameters = self.query_parameters::<P>();
+        parameters.push(json!({
+            "name": "Last-Event-ID",
+            "in": "header",
+            "required": false,
+            "schema": { "type": "integer", "format": "int64" },
+        }));
+        let operation = json!({
+            "description": "Server-sent events. Each event's data is a StreamMessage.",
+            "parameters": parameters,
+            "responses": {
+                "200": {
+                    "description": "an event stream",
+                    "content": {
+                        "text/event-stream": {
+                            "schema": self.schema_for::<response::StreamMessage<P::Item>>(),
+                        },
+                    },
+                },
+                "default": self.error_response(),
+            },
+        });
+        self.add(path, "get", operation);
+        self
+    }
+
+    // a route served by api::ws_adapter
+    // OpenAPI can't describe websockets, so the message schemas are given as extensions
+    pub fn ws<P, I>(mut self, path: &str) -> OpenApi
+    where
+        P: JsonSchema,
+        I: JsonSchema,
+    {
+        let operation = json!({
+            "description": "Websocket. The client sends the props as the first text frame, and receives StreamMessages.",
+            "x-websocket-request": self.schema_for::<P>(),
+            "x-websocket-message": self.schema_for::<response::StreamMessage<I>>(),
+            "responses": {
+                "101": { "description": "switching protocols" },
+                "default": self.error_response(),
+            },
+        });
+        self.add(path, "get", operation);
+        self
+    }
+
+    pub fn build(mut self, title: &str, version: &str) -> Value {
+        let schemas: Map<String, Value> = self
+            .gen
+            .take_definitions()
+            .into_iter()
+            .map(|(name, schema)| (name, to_value(schema)))
+            .collect();
+
+        json!({
+            "openapi": "3.0.3",
+            "info": {
+                "title": title,
+                "version": version,
+            },
+            "paths": self.paths,
+            "components": {
+                "schemas": schemas,
+                "securitySchemes": {
+                    "bearer": { "type": "http", "scheme": "bearer" },
+                },
+            },
+        })
+    }
+}
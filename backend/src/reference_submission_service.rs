@@ -0,0 +1,105 @@
+use crate::request::Strategy;
+
+use super::db_types::*;
+use std::convert::From;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for ReferenceSubmission {
+    // select * from reference_submission order only, otherwise it will fail
+    fn from(row: tokio_postgres::Row) -> ReferenceSubmission {
+        ReferenceSubmission {
+            reference_submission_id: row.get("reference_submission_id"),
+            creation_time: row.get("creation_time"),
+            submission_id: row.get("submission_id"),
+            strategy: (row.get::<_, i64>("strategy") as u8).try_into().unwrap(),
+        }
+    }
+}
+
+pub async fn add(
+    con: &mut impl GenericClient,
+    submission_id: i64,
+    strategy: Strategy,
+) -> Result<ReferenceSubmission, tokio_postgres::Error> {
+    let row = con
+        .query_one(
+            "INSERT INTO
+             reference_submission(
+                 submission_id,
+                 strategy
+             )
+             VALUES($1, $2)
+             RETURNING reference_submission_id, creation_time
+            ",
+            &[&submission_id, &(strategy.clone() as i64)],
+        )
+        .await?;
+
+    Ok(ReferenceSubmission {
+        reference_submission_id: row.get(0),
+        creation_time: row.get(1),
+        submission_id,
+        strategy,
+    })
+}
+
+// blocks other instances from installing reference submissions until the transaction ends
+pub async fn lock(con: &mut impl GenericClient) -> Result<(), tokio_postgres::Error> {
+    con.batch_execute("LOCK TABLE reference_submission IN SHARE ROW EXCLUSIVE MODE")
+        .await
+}
+
+pub async fn get_recent_by_strategy(
+    con: &mut impl GenericClient,
+    strategy: Strategy,
+) -> Result<Option<ReferenceSubmission>, tokio_postgres::Error> {
+    let result = con
+        .query_opt(
+            "SELECT * FROM recent_reference_submission WHERE strategy=$1",
+            &[&(strategy as i64)],
+        )
+        .await?
+        .map(|x| x.into());
+    Ok(result)
+}
+
+// includes reference submissions whose strategy has since been replaced
+pub async fn get_by_submission_ids(
+    con: &mut impl GenericClient,
+    submission_ids: &[i64],
+) -> Result<Vec<ReferenceSubmission>, tokio_postgres::Error> {
+    let result = con
+        .query(
+            "SELECT * FROM reference_submission WHERE submission_id = ANY($1)",
+            &[&submission_ids],
+        )
+        .await?
+        .into_iter()
+        .map(|x| x.into())
+        .collect();
+    Ok(result)
+}
+
+// oldest first
+pub async fn query(
+    con: &mut impl GenericClient,
+    only_recent: bool,
+) -> Result<Vec<ReferenceSubmission>, tokio_postgres::Error> {
+    let sql = [
+        if only_recent {
+            "SELECT * FROM recent_reference_submission"
+        } else {
+            "SELECT * FROM reference_submission"
+        },
+        "ORDER BY reference_submission_id",
+    ]
+    .join("\n");
+
+    let result = con
+        .query(&sql, &[])
+        .await?
+        .into_iter()
+        .map(|x| x.into())
+        .collect();
+    Ok(result)
+}
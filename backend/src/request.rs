@@ -1,8 +1,38 @@
-use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt::Display;
+use std::str::FromStr;
 
 use strum::AsRefStr;
 
-#[derive(Clone, Debug, Serialize, Deserialize, AsRefStr, PartialEq)]
+// accepts either a list or a comma separated string
+// this lets view props be read from a query string as well as a json body
+fn list<'de, D, T>(deserializer: D) -> Result<Option<Vec<T>>, D::Error>
+where
+  D: Deserializer<'de>,
+  T: Deserialize<'de> + FromStr,
+  T::Err: Display,
+{
+  #[derive(Deserialize)]
+  #[serde(untagged)]
+  enum List<T> {
+    Seq(Vec<T>),
+    Str(String),
+  }
+
+  match Option::<List<T>>::deserialize(deserializer)? {
+    None => Ok(None),
+    Some(List::Seq(v)) => Ok(Some(v)),
+    Some(List::Str(s)) => s
+      .split(',')
+      .filter(|x| !x.is_empty())
+      .map(|x| x.trim().parse().map_err(serde::de::Error::custom))
+      .collect::<Result<Vec<T>, D::Error>>()
+      .map(Some),
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, AsRefStr, PartialEq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum TournamentSubmissionKind {
   Compete,
@@ -24,14 +54,58 @@ impl TryFrom<u8> for TournamentSubmissionKind {
   }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SubmissionNewProps {
   pub code: String,
   pub api_key: String,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+// well known strategies, see strategies.rs
+// code can be tested against them, and organizers can enter them into tournaments as reference submissions
+// stored in the database as integers, so new strategies go at the end
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, AsRefStr, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Strategy {
+  AlwaysCooperate,
+  AlwaysDefect,
+  TitForTat,
+  GrimTrigger,
+  Random,
+  TitForTwoTats,
+  SuspiciousTitForTat,
+}
+
+impl TryFrom<u8> for Strategy {
+  type Error = u8;
+  fn try_from(val: u8) -> Result<Strategy, u8> {
+    match val {
+      x if x == Strategy::AlwaysCooperate as u8 => Ok(Strategy::AlwaysCooperate),
+      x if x == Strategy::AlwaysDefect as u8 => Ok(Strategy::AlwaysDefect),
+      x if x == Strategy::TitForTat as u8 => Ok(Strategy::TitForTat),
+      x if x == Strategy::GrimTrigger as u8 => Ok(Strategy::GrimTrigger),
+      x if x == Strategy::Random as u8 => Ok(Strategy::Random),
+      x if x == Strategy::TitForTwoTats as u8 => Ok(Strategy::TitForTwoTats),
+      x if x == Strategy::SuspiciousTitForTat as u8 => Ok(Strategy::SuspiciousTitForTat),
+      x => Err(x),
+    }
+  }
+}
+
+// plays code against an opponent without saving anything
+// exactly one of opponent_submission_id and opponent_strategy must be given
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmissionTestRunProps {
+  pub code: String,
+  // one of your own submissions, or one entered in a tournament as a testcase
+  pub opponent_submission_id: Option<i64>,
+  pub opponent_strategy: Option<Strategy>,
+  pub n_rounds: i64,
+  pub api_key: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TournamentNewProps {
   pub api_key: String,
@@ -39,9 +113,12 @@ pub struct TournamentNewProps {
   pub description: String,
   pub n_rounds: i64,
   pub n_matchups: i64,
+  // modules that entries may not import, such as os or urllib.request
+  #[serde(default)]
+  pub disallowed_imports: Vec<String>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TournamentDataNewProps {
   pub tournament_id: i64,
@@ -50,71 +127,264 @@ pub struct TournamentDataNewProps {
   pub n_rounds: i64,
   pub n_matchups: i64,
   pub active: bool,
+  // None keeps the current policy
+  pub disallowed_imports: Option<Vec<String>>,
   pub api_key: String,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TournamentSubmissionNewProps {
   pub tournament_id: i64,
   pub submission_id: i64,
   pub name: String,
   pub kind: TournamentSubmissionKind,
+  // only for Validate: automatically compete once all testcases pass
+  #[serde(default)]
+  pub auto_compete: bool,
   pub api_key: String,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+// enters the reference submission for a strategy, see install_reference_submissions
+// only the tournament's creator can do this, and reference entries can compete without validating
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentSubmissionNewReferenceProps {
+  pub tournament_id: i64,
+  pub strategy: Strategy,
+  // defaults to the strategy's name
+  pub name: Option<String>,
+  pub kind: TournamentSubmissionKind,
+  pub api_key: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentFinalizationNewProps {
+  pub tournament_id: i64,
+  pub n_rounds: i64,
+  pub n_matchups: i64,
+  pub api_key: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentSubmissionValidationReportProps {
+  pub tournament_id: i64,
+  pub submission_id: i64,
+  pub api_key: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SubmissionViewProps {
   pub submission_id: Option<Vec<i64>>,
   pub min_creation_time: Option<i64>,
   pub max_creation_time: Option<i64>,
   pub creator_user_id: Option<Vec<i64>>,
+  pub limit: Option<i64>,
+  pub cursor: Option<String>,
+  pub api_key: String,
+}
+
+// for GET routes that take no query parameters
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NoQueryProps {}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentDetailProps {
+  pub tournament_id: i64,
+  pub api_key: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentExportProps {
+  pub tournament_id: i64,
+  // replace user ids and entry names, for publishing datasets
+  #[serde(default)]
+  pub anonymize: bool,
   pub api_key: String,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TournamentDataViewProps {
+  #[serde(default, deserialize_with = "list")]
   pub tournament_data_id: Option<Vec<i64>>,
   pub min_creation_time: Option<i64>,
   pub max_creation_time: Option<i64>,
+  pub min_id: Option<i64>,
+  pub max_id: Option<i64>,
+  #[serde(default, deserialize_with = "list")]
   pub creator_user_id: Option<Vec<i64>>,
+  #[serde(default, deserialize_with = "list")]
   pub tournament_id: Option<Vec<i64>>,
+  #[serde(default, deserialize_with = "list")]
   pub title: Option<Vec<String>>,
   pub active: Option<bool>,
+  #[serde(default)]
   pub only_recent: bool,
+  pub limit: Option<i64>,
+  pub cursor: Option<String>,
+  // streams don't need an api key
+  #[serde(default)]
   pub api_key: String,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+// the whole library is small, so it isn't paged
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceSubmissionViewProps {
+  // leave out reference submissions whose strategy's code has since changed
+  #[serde(default)]
+  pub only_recent: bool,
+  #[serde(default)]
+  pub api_key: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TournamentSubmissionViewProps{
+  #[serde(default, deserialize_with = "list")]
   pub tournament_submission_id: Option<Vec<i64>>,
   pub min_creation_time: Option<i64>,
   pub max_creation_time: Option<i64>,
   pub min_id: Option<i64>,
   pub max_id: Option<i64>,
+  #[serde(default, deserialize_with = "list")]
   pub creator_user_id: Option<Vec<i64>>,
+  #[serde(default, deserialize_with = "list")]
   pub tournament_id: Option<Vec<i64>>,
+  #[serde(default, deserialize_with = "list")]
   pub submission_id: Option<Vec<i64>>,
   pub kind: Option<TournamentSubmissionKind>,
+  #[serde(default)]
   pub only_recent: bool,
+  pub limit: Option<i64>,
+  pub cursor: Option<String>,
+  // streams don't need an api key
+  #[serde(default)]
   pub api_key: String,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct MatchResolutionViewProps{
   pub min_creation_time: Option<i64>,
   pub max_creation_time: Option<i64>,
   pub min_id: Option<i64>,
   pub max_id: Option<i64>,
+  #[serde(default, deserialize_with = "list")]
   pub match_resolution_id: Option<Vec<i64>>,
+  #[serde(default, deserialize_with = "list")]
   pub submission_id: Option<Vec<i64>>,
+  #[serde(default, deserialize_with = "list")]
   pub opponent_submission_id: Option<Vec<i64>>,
+  #[serde(default, deserialize_with = "list")]
   pub round: Option<Vec<i64>>,
+  #[serde(default, deserialize_with = "list")]
   pub matchup: Option<Vec<i64>>,
+  pub tournament_finalization_id: Option<i64>,
+  #[serde(default)]
   pub only_recent: bool,
+  pub limit: Option<i64>,
+  pub cursor: Option<String>,
+  // streams don't need an api key
+  #[serde(default)]
   pub api_key: String,
 }
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultsFormat {
+  #[default]
+  Csv,
+  // one json object per line
+  Ndjson,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentResultsProps {
+  #[serde(default)]
+  pub format: ResultsFormat,
+  // practice results if unspecified, otherwise the tournament's final evaluation
+  pub tournament_finalization_id: Option<i64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchResolutionOutputProps {
+  pub match_resolution_id: i64,
+  pub api_key: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentFinalizationViewProps {
+  pub tournament_finalization_id: Option<Vec<i64>>,
+  pub min_creation_time: Option<i64>,
+  pub max_creation_time: Option<i64>,
+  pub creator_user_id: Option<Vec<i64>>,
+  pub tournament_id: Option<Vec<i64>>,
+  pub limit: Option<i64>,
+  pub cursor: Option<String>,
+  pub api_key: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentFinalizationEntryViewProps {
+  pub tournament_finalization_entry_id: Option<Vec<i64>>,
+  pub tournament_finalization_id: Option<Vec<i64>>,
+  pub tournament_submission_id: Option<Vec<i64>>,
+  pub submission_id: Option<Vec<i64>>,
+  pub limit: Option<i64>,
+  pub cursor: Option<String>,
+  pub api_key: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentStandingsStreamProps {
+  pub tournament_id: i64,
+  // practice play if unspecified, otherwise the given final evaluation
+  pub tournament_finalization_id: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn props(json: &str) -> Result<TournamentDataViewProps, serde_json::Error> {
+    serde_json::from_str(json)
+  }
+
+  #[test]
+  fn list_accepts_a_sequence() {
+    let x = props(r#"{"tournamentId": [1, 2]}"#).unwrap();
+    assert_eq!(x.tournament_id, Some(vec![1, 2]));
+  }
+
+  #[test]
+  fn list_accepts_a_comma_separated_string() {
+    let x = props(r#"{"tournamentId": "1, 2,,3", "title": "a,b"}"#).unwrap();
+    assert_eq!(x.tournament_id, Some(vec![1, 2, 3]));
+    assert_eq!(x.title, Some(vec![String::from("a"), String::from("b")]));
+  }
+
+  #[test]
+  fn list_is_none_when_missing_or_null() {
+    assert_eq!(props("{}").unwrap().tournament_id, None);
+    assert_eq!(props(r#"{"tournamentId": null}"#).unwrap().tournament_id, None);
+    assert_eq!(props(r#"{"tournamentId": ""}"#).unwrap().tournament_id, Some(vec![]));
+  }
+
+  #[test]
+  fn list_rejects_unparseable_items() {
+    assert!(props(r#"{"tournamentId": "1,x"}"#).is_err());
+  }
+}
@@ -1,23 +1,41 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use strum::AsRefStr;
 
-use crate::request::TournamentSubmissionKind;
+use crate::request::{Strategy, TournamentSubmissionKind};
 
-#[derive(Clone, Debug, Serialize, Deserialize, AsRefStr)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, AsRefStr)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum AppError {
     NoCapability,
     SubmissionNonexistent,
     TournamentNonexistent,
+    TournamentFinalizationNonexistent,
+    MatchResolutionNonexistent,
+    MatchResolutionOutputPruned,
     TournamentDataNRoundsInvalid,
     TournamentDataNMatchupsInvalid,
     TournamentDataTooManyMatches,
+    TournamentDataDisallowedImportInvalid,
     SubmissionTooLong,
+    SubmissionSyntaxError,
+    SubmissionShouldDefectInvalid,
+    SubmissionImportDisallowed,
+    SubmissionRateLimited,
+    TestRunNRoundsInvalid,
+    TestRunOpponentInvalid,
+    TournamentSubmissionCompeteLimit,
+    MatchBudgetExceeded,
+    RateLimited,
     TournamentSubmissionNotValidated,
     TournamentSubmissionTestcaseIncomplete,
     TournamentSubmissionTestcaseFails,
     TournamentArchived,
+    TournamentFinalized,
+    ArchiveVersionUnsupported,
+    ArchiveInvalid,
     StreamEndBeforeRequest,
+    CursorInvalid,
     DecodeError,
     MethodNotAllowed,
     InternalServerError,
@@ -36,7 +54,159 @@ impl std::fmt::Display for AppError {
 
 impl std::error::Error for AppError {}
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+impl AppError {
+    // a human readable description of the error
+    pub fn message(&self) -> &'static str {
+        match self {
+            AppError::NoCapability => "you don't have permission to do this",
+            AppError::SubmissionNonexistent => "the submission does not exist",
+            AppError::TournamentNonexistent => "the tournament does not exist",
+            AppError::TournamentFinalizationNonexistent => {
+                "the tournament has no final evaluation with that id"
+            }
+            AppError::MatchResolutionNonexistent => "the match resolution does not exist",
+            AppError::MatchResolutionOutputPruned => {
+                "the match's output is no longer kept, since it was replayed or its submission was cancelled"
+            }
+            AppError::TournamentDataNRoundsInvalid => "the number of rounds is out of range",
+            AppError::TournamentDataNMatchupsInvalid => "the number of matchups is out of range",
+            AppError::TournamentDataTooManyMatches => {
+                "the tournament would require too many matches"
+            }
+            AppError::TournamentDataDisallowedImportInvalid => {
+                "a disallowed import isn't a module name"
+            }
+            AppError::SubmissionTooLong => "the submission's code is too long",
+            AppError::SubmissionSyntaxError => "the submission's code has a syntax error",
+            AppError::SubmissionShouldDefectInvalid => {
+                "the submission doesn't define a should_defect function that can be called with two arguments"
+            }
+            AppError::SubmissionImportDisallowed => {
+                "the submission imports a module that the tournament doesn't allow"
+            }
+            AppError::SubmissionRateLimited => "you've made too many submissions recently",
+            AppError::TestRunNRoundsInvalid => "the number of rounds in a test run is out of range",
+            AppError::TestRunOpponentInvalid => {
+                "give exactly one of opponentSubmissionId and opponentStrategy"
+            }
+            AppError::TournamentSubmissionCompeteLimit => {
+                "you already have the maximum number of competing submissions in this tournament"
+            }
+            AppError::MatchBudgetExceeded => "you have too many matches queued",
+            AppError::RateLimited => "too many requests",
+            AppError::TournamentSubmissionNotValidated => {
+                "the submission must be validated before it can compete"
+            }
+            AppError::TournamentSubmissionTestcaseIncomplete => {
+                "the submission's matches against the testcases haven't finished yet"
+            }
+            AppError::TournamentSubmissionTestcaseFails => "the submission failed a testcase",
+            AppError::TournamentArchived => "the tournament is archived",
+            AppError::TournamentFinalized => "the tournament has been finalized",
+            AppError::ArchiveVersionUnsupported => {
+                "the archive was exported by an incompatible version"
+            }
+            AppError::ArchiveInvalid => "the archive refers to rows that aren't in it",
+            AppError::StreamEndBeforeRequest => "the stream closed before the props were sent",
+            AppError::CursorInvalid => "the cursor is invalid",
+            AppError::DecodeError => "the request could not be decoded",
+            AppError::MethodNotAllowed => "method not allowed",
+            AppError::InternalServerError => "internal server error",
+            AppError::Unauthorized => "the api key is missing, invalid or expired",
+            AppError::BadRequest => "bad request",
+            AppError::NotFound => "not found",
+            AppError::Network => "a network error occurred",
+            AppError::Unknown => "an unknown error occurred",
+        }
+    }
+
+    // whether the same request might succeed if it is retried later
+    pub fn retryable(&self) -> bool {
+        matches!(
+            self,
+            AppError::SubmissionRateLimited
+                | AppError::MatchBudgetExceeded
+                | AppError::RateLimited
+                | AppError::TournamentSubmissionTestcaseIncomplete
+                | AppError::InternalServerError
+                | AppError::Network
+                | AppError::Unknown
+        )
+    }
+}
+
+// extra information about some errors
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorDetails {
+    #[serde(rename_all = "camelCase")]
+    Testcase {
+        testcase_tournament_submission_id: i64,
+        testcase_submission_id: i64,
+        // the match that failed, if any
+        match_resolution_id: Option<i64>,
+    },
+    // where the problem is in the submission's code, and what to do about it
+    #[serde(rename_all = "camelCase")]
+    Code {
+        line: Option<i64>,
+        column: Option<i64>,
+        message: String,
+    },
+}
+
+// the body of every error response
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorResponse {
+    pub code: AppError,
+    pub message: String,
+    pub retryable: bool,
+    pub details: Option<ErrorDetails>,
+}
+
+impl ErrorResponse {
+    pub fn with_details(code: AppError, details: ErrorDetails) -> ErrorResponse {
+        ErrorResponse {
+            details: Some(details),
+            ..code.into()
+        }
+    }
+}
+
+impl From<AppError> for ErrorResponse {
+    fn from(code: AppError) -> ErrorResponse {
+        ErrorResponse {
+            message: code.message().to_owned(),
+            retryable: code.retryable(),
+            details: None,
+            code,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+// messages sent over a stream
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", content = "item", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum StreamMessage<T> {
+    // an item in the view
+    Item(T),
+    // every item that existed when the stream (re)started has been sent
+    SnapshotComplete,
+    // the server fell behind and is about to resend items since the last one sent
+    Resync,
+    // sent periodically so that clients can detect dead connections
+    Heartbeat,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Submission {
     pub submission_id: i64,
@@ -45,7 +215,7 @@ pub struct Submission {
     pub code: String,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Tournament {
     pub tournament_id: i64,
@@ -53,7 +223,7 @@ pub struct Tournament {
     pub creator_user_id: i64,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TournamentData {
     pub tournament_data_id: i64,
@@ -65,9 +235,11 @@ pub struct TournamentData {
     pub n_rounds: i64,
     pub n_matchups: i64,
     pub active: bool,
+    // modules that entries may not import, submodules included
+    pub disallowed_imports: Vec<String>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TournamentSubmission {
     pub tournament_submission_id: i64,
@@ -77,9 +249,99 @@ pub struct TournamentSubmission {
     pub submission_id: i64,
     pub name: String,
     pub kind: TournamentSubmissionKind,
+    pub auto_compete: bool,
+    // the strategy played, if the entry is a reference submission
+    pub reference_strategy: Option<Strategy>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceSubmission {
+    pub reference_submission_id: i64,
+    pub creation_time: i64,
+    pub submission: Submission,
+    pub strategy: Strategy,
+    // what the entry is called unless the organizer names it
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationFailure {
+    pub match_resolution_id: i64,
+    // the program that failed
+    pub submission_id: i64,
+    pub opponent_submission_id: i64,
+    pub matchup: i64,
+    pub round: i64,
+    // the end of the failing program's stderr
+    pub stderr_excerpt: String,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TestcaseReport {
+    pub testcase: TournamentSubmission,
+    pub n_completed_rounds: i64,
+    pub n_expected_rounds: i64,
+    pub failures: Vec<ValidationFailure>,
+}
+
+// how a submission is doing against each of a tournament's testcases
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationReport {
+    pub tournament_id: i64,
+    pub submission_id: i64,
+    // whether the submission can be promoted to COMPETE
+    pub passed: bool,
+    pub testcases: Vec<TestcaseReport>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentFinalization {
+    pub tournament_finalization_id: i64,
+    pub creation_time: i64,
+    pub creator_user_id: i64,
+    pub tournament: Tournament,
+    pub n_rounds: i64,
+    pub n_matchups: i64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentSummary {
+    pub n_compete: i64,
+    pub n_validate: i64,
+    pub n_testcase: i64,
+    pub n_cancel: i64,
+    pub finalized: bool,
+}
+
+// everything needed to render a tournament's page in one response
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentDetail {
+    pub tournament_data: TournamentData,
+    // the most recent tournament submission of each submission
+    pub tournament_submissions: Vec<TournamentSubmission>,
+    pub tournament_finalization: Option<TournamentFinalization>,
+    pub summary: TournamentSummary,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentFinalizationEntry {
+    pub tournament_finalization_entry_id: i64,
+    pub creation_time: i64,
+    pub tournament_finalization_id: i64,
+    pub tournament_submission_id: i64,
+    pub submission_id: i64,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct MatchResolutionLite {
     pub match_resolution_id: i64,
@@ -88,10 +350,11 @@ pub struct MatchResolutionLite {
     pub opponent_submission_id: i64,
     pub round: i64,
     pub matchup: i64,
+    pub tournament_finalization_id: Option<i64>,
     pub defected: Option<bool>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct MatchResolution {
     pub match_resolution_id: i64,
@@ -100,13 +363,67 @@ pub struct MatchResolution {
     pub opponent_submission_id: i64,
     pub round: i64,
     pub matchup: i64,
+    pub tournament_finalization_id: Option<i64>,
     pub defected: Option<bool>,
+    // what python's random was seeded with, None for matches played before bots were seeded
+    pub seed: Option<i64>,
+}
+
+// what the tested code did in one round
+// the opponent's output isn't included, since its code may not be yours
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TestRunRound {
+    pub round: i64,
+    pub defected: Option<bool>,
+    pub opponent_defected: Option<bool>,
+    pub payoff: Option<i64>,
+    pub opponent_payoff: Option<i64>,
     pub stdout: String,
+    pub stdout_truncated: bool,
     pub stderr: String,
+    pub stderr_truncated: bool,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmissionTestRun {
+    pub rounds: Vec<TestRunRound>,
+}
+
+// one round of a matchup, as seen from the submission with the lower id
+// a side that hasn't been resolved yet, or whose bot failed, has no move and no payoff
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchResult {
+    pub tournament_id: i64,
+    pub tournament_title: String,
+    pub tournament_finalization_id: Option<i64>,
+    pub submission_id: i64,
+    pub submission_name: String,
+    pub opponent_submission_id: i64,
+    pub opponent_submission_name: String,
+    pub matchup: i64,
+    pub round: i64,
+    pub defected: Option<bool>,
+    pub opponent_defected: Option<bool>,
+    pub payoff: Option<i64>,
+    pub opponent_payoff: Option<i64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchResolutionOutput {
+    pub match_resolution_id: i64,
+    pub stdout: String,
+    // whether the end of stdout was cut off
+    pub stdout_truncated: bool,
+    pub stderr: String,
+    // whether the start of stderr was cut off
+    pub stderr_truncated: bool,
+}
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Info {
     pub service: String,
@@ -114,3 +431,23 @@ pub struct Info {
     pub version_minor: i64,
     pub version_rev: i64,
 }
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Standing {
+    pub tournament_submission_id: i64,
+    pub submission_id: i64,
+    pub name: String,
+    // the strategy played, if the entry is a reference submission
+    pub reference_strategy: Option<Strategy>,
+    // None until a round against a competing entry has been scored
+    pub score: Option<f64>,
+    // change in score since the last update sent for this entry
+    pub score_delta: Option<f64>,
+    pub rank: i64,
+    // None the first time the entry is sent
+    pub previous_rank: Option<i64>,
+    pub n_scored_rounds: i64,
+    // the entry no longer competes and should be removed from the leaderboard
+    pub withdrawn: bool,
+}
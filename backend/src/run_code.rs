@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use base64::Engine;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
@@ -29,6 +30,22 @@ pub struct SubmissionSummary {
     pub exit_code: Option<i64>,
 }
 
+// packs the files into the tar that the executor unpacks and runs
+pub fn tar_files(map: HashMap<String, String>) -> Result<Vec<u8>, response::AppError> {
+    let mut archive = tar::Builder::new(vec![]);
+
+    for (file_name, file_content) in map {
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(0o777);
+        header.set_size(file_content.len() as u64);
+        archive
+            .append_data(&mut header, file_name, file_content.as_bytes())
+            .map_err(report_io_err)?;
+    }
+
+    archive.into_inner().map_err(report_io_err)
+}
+
 #[derive(Clone)]
 pub struct RunCodeService {
     client: Client,
@@ -67,32 +84,28 @@ impl RunCodeService {
         &self,
         map: HashMap<String, String>,
     ) -> Result<SubmissionSummary, response::AppError> {
-        let mut archive = tar::Builder::new(vec![]);
-
-        for (file_name, file_content) in map {
-            let mut header = tar::Header::new_gnu();
-            header.set_mode(0o777);
-            header.set_size(file_content.as_bytes().len() as u64);
-            archive
-                .append_data(&mut header, file_name, file_content.as_bytes())
-                .map_err(report_io_err)?;
-        }
-
-        let tar_buf = archive.into_inner().map_err(report_io_err)?;
+        let tar_buf = tar_files(map)?;
 
         let x = self
             .send_submission(RunCodeRequest {
                 max_time_s: super::MAX_TIME,
-                base_64_tar_gz: base64::encode(&tar_buf),
+                base_64_tar_gz: base64::engine::general_purpose::STANDARD.encode(&tar_buf),
             })
-            .await
-            .unwrap();
+            .await?;
 
         let summary = SubmissionSummary {
-            stdout: String::from_utf8_lossy(&base64::decode(x.stdout).map_err(report_base64_err)?)
-                .to_string(),
-            stderr: String::from_utf8_lossy(&base64::decode(x.stderr).map_err(report_base64_err)?)
-                .to_string(),
+            stdout: String::from_utf8_lossy(
+                &base64::engine::general_purpose::STANDARD
+                    .decode(x.stdout)
+                    .map_err(report_base64_err)?,
+            )
+            .to_string(),
+            stderr: String::from_utf8_lossy(
+                &base64::engine::general_purpose::STANDARD
+                    .decode(x.stderr)
+                    .map_err(report_base64_err)?,
+            )
+            .to_string(),
             exit_code: x.exit_code,
         };
 
@@ -0,0 +1,218 @@
+use super::request::Strategy;
+use super::response;
+use std::collections::HashMap;
+
+// mirrors scorePrisonersDilemma in the web frontend
+pub fn score_prisoners_dilemma(defected: bool, opponent_defected: bool) -> i64 {
+    match (defected, opponent_defected) {
+        // both defect
+        (true, true) => 5,
+        // you defect, partner cooperates
+        (true, false) => 10,
+        // you cooperate, your partner defects
+        (false, true) => 0,
+        // both cooperate
+        (false, false) => 8,
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StandingsEntry {
+    pub tournament_submission_id: i64,
+    pub submission_id: i64,
+    pub name: String,
+    pub reference_strategy: Option<Strategy>,
+}
+
+// (submission_id, opponent_submission_id)
+type Pair = (i64, i64);
+// (submission_id, opponent_submission_id, matchup, round)
+type Cell = (i64, i64, i64, i64);
+
+// Running leaderboard for a set of competing entries.
+// A submission's score is the mean, over opponents it has played, of its average score per round,
+// which is how the cross table in the web frontend ranks submissions.
+pub struct Standings {
+    // keyed by submission id
+    entries: HashMap<i64, StandingsEntry>,
+    defected: HashMap<Cell, Option<bool>>,
+    // sum of scores and number of scored rounds
+    pairs: HashMap<Pair, (i64, i64)>,
+    // the standings as of the last call to publish, keyed by submission id
+    published: HashMap<i64, response::Standing>,
+}
+
+impl Standings {
+    pub fn new(entries: Vec<StandingsEntry>) -> Standings {
+        Standings {
+            entries: entries.into_iter().map(|x| (x.submission_id, x)).collect(),
+            defected: HashMap::new(),
+            pairs: HashMap::new(),
+            published: HashMap::new(),
+        }
+    }
+
+    // replaces the competing entries, keeping what was last published so the next diff is correct
+    pub fn with_published(mut self, previous: Standings) -> Standings {
+        self.published = previous.published;
+        self
+    }
+
+    fn cell_score(
+        &self,
+        (submission_id, opponent_submission_id, matchup, round): Cell,
+    ) -> Option<i64> {
+        let defected =
+            self.defected
+                .get(&(submission_id, opponent_submission_id, matchup, round))?;
+        let opponent_defected =
+            self.defected
+                .get(&(opponent_submission_id, submission_id, matchup, round))?;
+        match (defected, opponent_defected) {
+            (Some(d), Some(o)) => Some(score_prisoners_dilemma(*d, *o)),
+            _ => None,
+        }
+    }
+
+    // records a resolution, replacing any earlier resolution of the same round
+    // returns false if it doesn't involve two competing entries
+    pub fn apply(&mut self, match_resolution: &response::MatchResolutionLite) -> bool {
+        let submission_id = match_resolution.submission_id;
+        let opponent_submission_id = match_resolution.opponent_submission_id;
+        if !self.entries.contains_key(&submission_id)
+            || !self.entries.contains_key(&opponent_submission_id)
+        {
+            return false;
+        }
+
+        let matchup = match_resolution.matchup;
+        let round = match_resolution.round;
+
+        // a resolution affects the round as seen from both sides (only one side when self-playing)
+        let mut cells = vec![(submission_id, opponent_submission_id, matchup, round)];
+        if submission_id != opponent_submission_id {
+            cells.push((opponent_submission_id, submission_id, matchup, round));
+        }
+
+        for &cell in cells.iter() {
+            if let Some(score) = self.cell_score(cell) {
+                let pair = self.pairs.entry((cell.0, cell.1)).or_insert((0, 0));
+                pair.0 -= score;
+                pair.1 -= 1;
+            }
+        }
+
+        self.defected.insert(
+            (submission_id, opponent_submission_id, matchup, round),
+            match_resolution.defected,
+        );
+
+        for &cell in cells.iter() {
+            if let Some(score) = self.cell_score(cell) {
+                let pair = self.pairs.entry((cell.0, cell.1)).or_insert((0, 0));
+                pair.0 += score;
+                pair.1 += 1;
+            }
+        }
+
+        true
+    }
+
+    fn current(&self) -> Vec<response::Standing> {
+        let mut standings: Vec<response::Standing> = self
+            .entries
+            .values()
+            .map(|entry| {
+                let mut total = 0.0;
+                let mut n_opponents = 0;
+                let mut n_scored_rounds = 0;
+                for opponent_submission_id in self.entries.keys() {
+                    if let Some(&(sum, count)) = self
+                        .pairs
+                        .get(&(entry.submission_id, *opponent_submission_id))
+                    {
+                        if count > 0 {
+                            total += sum as f64 / count as f64;
+                            n_opponents += 1;
+                            n_scored_rounds += count;
+                        }
+                    }
+                }
+                response::Standing {
+                    tournament_submission_id: entry.tournament_submission_id,
+                    submission_id: entry.submission_id,
+                    name: entry.name.clone(),
+                    reference_strategy: entry.reference_strategy.clone(),
+                    score: if n_opponents > 0 {
+                        Some(total / n_opponents as f64)
+                    } else {
+                        None
+                    },
+                    score_delta: None,
+                    rank: 0,
+                    previous_rank: None,
+                    n_scored_rounds,
+                    withdrawn: false,
+                }
+            })
+            .collect();
+
+        // highest score first, unscored entries last
+        standings.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.tournament_submission_id.cmp(&b.tournament_submission_id))
+        });
+
+        // tied entries share a rank
+        for i in 0..standings.len() {
+            standings[i].rank = if i > 0 && standings[i].score == standings[i - 1].score {
+                standings[i - 1].rank
+            } else {
+                i as i64 + 1
+            };
+        }
+
+        standings
+    }
+
+    // returns the entries that changed since the last call, ordered by rank
+    pub fn publish(&mut self) -> Vec<response::Standing> {
+        let mut changed = vec![];
+
+        let current = self.current();
+        for mut standing in current.iter().cloned() {
+            if let Some(previous) = self.published.get(&standing.submission_id) {
+                if previous.score == standing.score
+                    && previous.rank == standing.rank
+                    && previous.n_scored_rounds == standing.n_scored_rounds
+                {
+                    continue;
+                }
+                standing.score_delta = match (standing.score, previous.score) {
+                    (Some(score), Some(previous_score)) => Some(score - previous_score),
+                    _ => None,
+                };
+                standing.previous_rank = Some(previous.rank);
+            }
+            changed.push(standing);
+        }
+
+        // entries that stopped competing
+        for previous in self.published.values() {
+            if !self.entries.contains_key(&previous.submission_id) {
+                changed.push(response::Standing {
+                    score_delta: None,
+                    previous_rank: Some(previous.rank),
+                    withdrawn: true,
+                    ..previous.clone()
+                });
+            }
+        }
+
+        self.published = current.into_iter().map(|x| (x.submission_id, x)).collect();
+
+        changed
+    }
+}
@@ -0,0 +1,70 @@
+use super::request::Strategy;
+
+// the reference submissions installed by install_reference_submissions, in handlers.rs
+pub static ALL: &[Strategy] = &[
+    Strategy::AlwaysCooperate,
+    Strategy::AlwaysDefect,
+    Strategy::TitForTat,
+    Strategy::GrimTrigger,
+    Strategy::Random,
+    Strategy::TitForTwoTats,
+    Strategy::SuspiciousTitForTat,
+];
+
+// the default name of a reference entry
+pub fn name(strategy: &Strategy) -> &'static str {
+    match strategy {
+        Strategy::AlwaysCooperate => "Always Cooperate",
+        Strategy::AlwaysDefect => "Always Defect",
+        Strategy::TitForTat => "Tit for Tat",
+        Strategy::GrimTrigger => "Grim Trigger",
+        Strategy::Random => "Random",
+        Strategy::TitForTwoTats => "Tit for Two Tats",
+        Strategy::SuspiciousTitForTat => "Suspicious Tit for Tat",
+    }
+}
+
+// the code of each well known strategy, as a bot would submit it
+pub fn code(strategy: &Strategy) -> &'static str {
+    match strategy {
+        Strategy::AlwaysCooperate => {
+            "def should_defect(opp_defection_function, opponent_defection_history):
+    return False
+"
+        }
+        Strategy::AlwaysDefect => {
+            "def should_defect(opp_defection_function, opponent_defection_history):
+    return True
+"
+        }
+        // an opponent that failed to respond counts as having cooperated
+        Strategy::TitForTat => {
+            "def should_defect(opp_defection_function, opponent_defection_history):
+    return len(opponent_defection_history) > 0 and opponent_defection_history[-1] is True
+"
+        }
+        Strategy::GrimTrigger => {
+            "def should_defect(opp_defection_function, opponent_defection_history):
+    return True in opponent_defection_history
+"
+        }
+        Strategy::Random => {
+            "import random
+
+def should_defect(opp_defection_function, opponent_defection_history):
+    return random.random() < 0.5
+"
+        }
+        Strategy::TitForTwoTats => {
+            "def should_defect(opp_defection_function, opponent_defection_history):
+    return opponent_defection_history[-2:] == [True, True]
+"
+        }
+        // defects first, then plays tit for tat
+        Strategy::SuspiciousTitForTat => {
+            "def should_defect(opp_defection_function, opponent_defection_history):
+    return len(opponent_defection_history) == 0 or opponent_defection_history[-1] is True
+"
+        }
+    }
+}
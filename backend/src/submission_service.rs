@@ -1,4 +1,5 @@
 use super::db_types::*;
+use super::utils;
 use tokio_postgres::GenericClient;
 
 impl From<tokio_postgres::row::Row> for Submission {
@@ -55,6 +56,67 @@ pub async fn get_by_submission_id(
     Ok(result)
 }
 
+pub async fn get_by_submission_ids(
+    con: &mut impl GenericClient,
+    submission_ids: &[i64],
+) -> Result<Vec<Submission>, tokio_postgres::Error> {
+    let result = con
+        .query(
+            "SELECT * FROM submission WHERE submission_id = ANY($1) ORDER BY submission_id",
+            &[&submission_ids],
+        )
+        .await?
+        .into_iter()
+        .map(|x| x.into())
+        .collect();
+    Ok(result)
+}
+
+// inserts a copy of an archived submission under a new id, keeping its creation time
+pub async fn restore(
+    con: &mut impl GenericClient,
+    submission: Submission,
+) -> Result<Submission, tokio_postgres::Error> {
+    let row = con
+        .query_one(
+            "INSERT INTO
+             submission(
+                 creation_time,
+                 creator_user_id,
+                 code
+             )
+             VALUES($1, $2, $3)
+             RETURNING submission_id
+            ",
+            &[
+                &submission.creation_time,
+                &submission.creator_user_id,
+                &submission.code,
+            ],
+        )
+        .await?;
+
+    Ok(Submission {
+        submission_id: row.get(0),
+        ..submission
+    })
+}
+
+// counts the submissions a user has made since min_creation_time
+pub async fn count_by_creator_user_id(
+    con: &mut impl GenericClient,
+    creator_user_id: i64,
+    min_creation_time: i64,
+) -> Result<i64, tokio_postgres::Error> {
+    let row = con
+        .query_one(
+            "SELECT COUNT(*) FROM submission WHERE creator_user_id=$1 AND creation_time >= $2",
+            &[&creator_user_id, &min_creation_time],
+        )
+        .await?;
+    Ok(row.get(0))
+}
+
 pub async fn query(
     con: &mut impl GenericClient,
     props: super::request::SubmissionViewProps,
@@ -66,7 +128,10 @@ pub async fn query(
         " AND ($2::bigint   IS NULL OR s.creation_time <= $2)",
         " AND ($3::bigint[] IS NULL OR s.submission_id = ANY($3))",
         " AND ($4::bigint[] IS NULL OR s.creator_user_id = ANY($4))",
+        // start after the cursor
+        " AND ($5::bigint   IS NULL OR s.submission_id > $5)",
         " ORDER BY s.submission_id",
+        " LIMIT $6",
     ]
     .join("\n");
 
@@ -80,6 +145,8 @@ pub async fn query(
                 &props.max_creation_time,
                 &props.submission_id,
                 &props.creator_user_id,
+                &props.cursor.as_deref().and_then(utils::decode_cursor),
+                &props.limit,
             ],
         )
         .await?
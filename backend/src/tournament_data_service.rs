@@ -1,5 +1,6 @@
 use super::db_types::*;
 use super::request;
+use super::utils;
 use tokio_postgres::GenericClient;
 
 impl From<tokio_postgres::row::Row> for TournamentData {
@@ -15,10 +16,12 @@ impl From<tokio_postgres::row::Row> for TournamentData {
             n_rounds: row.get("n_rounds"),
             n_matchups: row.get("n_matchups"),
             active: row.get("active"),
+            disallowed_imports: row.get("disallowed_imports"),
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn add(
     con: &mut impl GenericClient,
     creator_user_id: i64,
@@ -28,6 +31,7 @@ pub async fn add(
     n_rounds: i64,
     n_matchups: i64,
     active: bool,
+    disallowed_imports: Vec<String>,
 ) -> Result<TournamentData, tokio_postgres::Error> {
     let row = con
         .query_one(
@@ -39,9 +43,10 @@ pub async fn add(
                  description,
                  n_rounds,
                  n_matchups,
-                 active
+                 active,
+                 disallowed_imports
              )
-             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
              RETURNING tournament_data_id, creation_time
             ",
             &[
@@ -52,6 +57,7 @@ pub async fn add(
                 &n_rounds,
                 &n_matchups,
                 &active,
+                &disallowed_imports,
             ],
         )
         .await?;
@@ -67,9 +73,24 @@ pub async fn add(
         n_rounds,
         n_matchups,
         active,
+        disallowed_imports,
     })
 }
 
+pub async fn get_by_tournament_data_id(
+    con: &mut impl GenericClient,
+    tournament_data_id: i64,
+) -> Result<Option<TournamentData>, tokio_postgres::Error> {
+    let result = con
+        .query_opt(
+            "SELECT * FROM tournament_data WHERE tournament_data_id=$1",
+            &[&tournament_data_id],
+        )
+        .await?
+        .map(|x| x.into());
+    Ok(result)
+}
+
 pub async fn get_recent_by_tournament_id(
     con: &mut impl GenericClient,
     tournament_id: i64,
@@ -92,6 +113,65 @@ pub async fn get_recent_by_tournament_id(
     Ok(results)
 }
 
+// every revision of the tournament's data, oldest first
+pub async fn get_by_tournament_id(
+    con: &mut impl GenericClient,
+    tournament_id: i64,
+) -> Result<Vec<TournamentData>, tokio_postgres::Error> {
+    let result = con
+        .query(
+            "SELECT * FROM tournament_data WHERE tournament_id=$1 ORDER BY tournament_data_id",
+            &[&tournament_id],
+        )
+        .await?
+        .into_iter()
+        .map(|x| x.into())
+        .collect();
+    Ok(result)
+}
+
+// inserts a copy of an archived tournament data under a new id, keeping its creation time
+pub async fn restore(
+    con: &mut impl GenericClient,
+    tournament_data: TournamentData,
+) -> Result<TournamentData, tokio_postgres::Error> {
+    let row = con
+        .query_one(
+            "INSERT INTO
+             tournament_data(
+                 creation_time,
+                 creator_user_id,
+                 tournament_id,
+                 title,
+                 description,
+                 n_rounds,
+                 n_matchups,
+                 active,
+                 disallowed_imports
+             )
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+             RETURNING tournament_data_id
+            ",
+            &[
+                &tournament_data.creation_time,
+                &tournament_data.creator_user_id,
+                &tournament_data.tournament_id,
+                &tournament_data.title,
+                &tournament_data.description,
+                &tournament_data.n_rounds,
+                &tournament_data.n_matchups,
+                &tournament_data.active,
+                &tournament_data.disallowed_imports,
+            ],
+        )
+        .await?;
+
+    Ok(TournamentData {
+        tournament_data_id: row.get(0),
+        ..tournament_data
+    })
+}
+
 pub async fn query(
     con: &mut impl GenericClient,
     props: request::TournamentDataViewProps,
@@ -110,7 +190,12 @@ pub async fn query(
         " AND ($5::bigint[]  IS NULL OR td.tournament_id = ANY($5))",
         " AND ($6::text[]    IS NULL OR td.title = ANY($6))",
         " AND ($7::bool      IS NULL OR td.active = $7)",
+        " AND ($8::bigint    IS NULL OR td.tournament_data_id >= $8)",
+        " AND ($9::bigint    IS NULL OR td.tournament_data_id <= $9)",
+        // start after the cursor
+        " AND ($10::bigint   IS NULL OR td.tournament_data_id > $10)",
         " ORDER BY td.tournament_data_id",
+        " LIMIT $11",
     ]
     .join("\n");
 
@@ -127,6 +212,10 @@ pub async fn query(
                 &props.tournament_id,
                 &props.title,
                 &props.active,
+                &props.min_id,
+                &props.max_id,
+                &props.cursor.as_deref().and_then(utils::decode_cursor),
+                &props.limit,
             ],
         )
         .await?
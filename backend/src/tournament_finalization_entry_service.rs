@@ -0,0 +1,147 @@
+use super::db_types::*;
+use super::request;
+use super::utils;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for TournamentFinalizationEntry {
+    // select * from tournament_finalization_entry order only, otherwise it will fail
+    fn from(row: tokio_postgres::Row) -> TournamentFinalizationEntry {
+        TournamentFinalizationEntry {
+            tournament_finalization_entry_id: row.get("tournament_finalization_entry_id"),
+            creation_time: row.get("creation_time"),
+            tournament_finalization_id: row.get("tournament_finalization_id"),
+            tournament_submission_id: row.get("tournament_submission_id"),
+            submission_id: row.get("submission_id"),
+            name: row.get("name"),
+        }
+    }
+}
+
+pub async fn add(
+    con: &mut impl GenericClient,
+    tournament_finalization_id: i64,
+    tournament_submission_id: i64,
+    submission_id: i64,
+    name: String,
+) -> Result<TournamentFinalizationEntry, tokio_postgres::Error> {
+    let row = con
+        .query_one(
+            "INSERT INTO
+             tournament_finalization_entry(
+                 tournament_finalization_id,
+                 tournament_submission_id,
+                 submission_id,
+                 name
+             )
+             VALUES ($1, $2, $3, $4)
+             RETURNING tournament_finalization_entry_id, creation_time
+            ",
+            &[
+                &tournament_finalization_id,
+                &tournament_submission_id,
+                &submission_id,
+                &name,
+            ],
+        )
+        .await?;
+
+    // return tournament_finalization_entry
+    Ok(TournamentFinalizationEntry {
+        tournament_finalization_entry_id: row.get(0),
+        creation_time: row.get(1),
+        tournament_finalization_id,
+        tournament_submission_id,
+        submission_id,
+        name,
+    })
+}
+
+pub async fn get_by_tournament_finalization_id(
+    con: &mut impl GenericClient,
+    tournament_finalization_id: i64,
+) -> Result<Vec<TournamentFinalizationEntry>, tokio_postgres::Error> {
+    let result = con
+        .query(
+            "SELECT * FROM tournament_finalization_entry WHERE tournament_finalization_id=$1 ORDER BY tournament_finalization_entry_id",
+            &[&tournament_finalization_id],
+        )
+        .await?
+        .into_iter()
+        .map(|x| x.into())
+        .collect();
+    Ok(result)
+}
+
+// inserts a copy of an archived tournament finalization entry under a new id, keeping its creation time
+pub async fn restore(
+    con: &mut impl GenericClient,
+    tournament_finalization_entry: TournamentFinalizationEntry,
+) -> Result<TournamentFinalizationEntry, tokio_postgres::Error> {
+    let row = con
+        .query_one(
+            "INSERT INTO
+             tournament_finalization_entry(
+                 creation_time,
+                 tournament_finalization_id,
+                 tournament_submission_id,
+                 submission_id,
+                 name
+             )
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING tournament_finalization_entry_id
+            ",
+            &[
+                &tournament_finalization_entry.creation_time,
+                &tournament_finalization_entry.tournament_finalization_id,
+                &tournament_finalization_entry.tournament_submission_id,
+                &tournament_finalization_entry.submission_id,
+                &tournament_finalization_entry.name,
+            ],
+        )
+        .await?;
+
+    Ok(TournamentFinalizationEntry {
+        tournament_finalization_entry_id: row.get(0),
+        ..tournament_finalization_entry
+    })
+}
+
+pub async fn query(
+    con: &mut impl GenericClient,
+    props: request::TournamentFinalizationEntryViewProps,
+) -> Result<Vec<TournamentFinalizationEntry>, tokio_postgres::Error> {
+    let sql = [
+        "SELECT tfe.* FROM tournament_finalization_entry tfe",
+        " WHERE 1 = 1",
+        " AND ($1::bigint[] IS NULL OR tfe.tournament_finalization_entry_id = ANY($1))",
+        " AND ($2::bigint[] IS NULL OR tfe.tournament_finalization_id = ANY($2))",
+        " AND ($3::bigint[] IS NULL OR tfe.tournament_submission_id = ANY($3))",
+        " AND ($4::bigint[] IS NULL OR tfe.submission_id = ANY($4))",
+        // start after the cursor
+        " AND ($5::bigint   IS NULL OR tfe.tournament_finalization_entry_id > $5)",
+        " ORDER BY tfe.tournament_finalization_entry_id",
+        " LIMIT $6",
+    ]
+    .join("\n");
+
+    let stmnt = con.prepare(&sql).await?;
+
+    let results = con
+        .query(
+            &stmnt,
+            &[
+                &props.tournament_finalization_entry_id,
+                &props.tournament_finalization_id,
+                &props.tournament_submission_id,
+                &props.submission_id,
+                &props.cursor.as_deref().and_then(utils::decode_cursor),
+                &props.limit,
+            ],
+        )
+        .await?
+        .into_iter()
+        .map(|row| row.into())
+        .collect();
+
+    Ok(results)
+}
@@ -0,0 +1,142 @@
+use super::db_types::*;
+use super::request;
+use super::utils;
+use tokio_postgres::GenericClient;
+
+impl From<tokio_postgres::row::Row> for TournamentFinalization {
+    // select * from tournament_finalization order only, otherwise it will fail
+    fn from(row: tokio_postgres::Row) -> TournamentFinalization {
+        TournamentFinalization {
+            tournament_finalization_id: row.get("tournament_finalization_id"),
+            creation_time: row.get("creation_time"),
+            creator_user_id: row.get("creator_user_id"),
+            tournament_id: row.get("tournament_id"),
+            n_rounds: row.get("n_rounds"),
+            n_matchups: row.get("n_matchups"),
+        }
+    }
+}
+
+pub async fn add(
+    con: &mut impl GenericClient,
+    creator_user_id: i64,
+    tournament_id: i64,
+    n_rounds: i64,
+    n_matchups: i64,
+) -> Result<TournamentFinalization, tokio_postgres::Error> {
+    let row = con
+        .query_one(
+            "INSERT INTO
+             tournament_finalization(
+                 creator_user_id,
+                 tournament_id,
+                 n_rounds,
+                 n_matchups
+             )
+             VALUES ($1, $2, $3, $4)
+             RETURNING tournament_finalization_id, creation_time
+            ",
+            &[&creator_user_id, &tournament_id, &n_rounds, &n_matchups],
+        )
+        .await?;
+
+    // return tournament_finalization
+    Ok(TournamentFinalization {
+        tournament_finalization_id: row.get(0),
+        creation_time: row.get(1),
+        creator_user_id,
+        tournament_id,
+        n_rounds,
+        n_matchups,
+    })
+}
+
+pub async fn get_by_tournament_id(
+    con: &mut impl GenericClient,
+    tournament_id: i64,
+) -> Result<Option<TournamentFinalization>, tokio_postgres::Error> {
+    let result = con
+        .query_opt(
+            "SELECT * FROM tournament_finalization WHERE tournament_id=$1",
+            &[&tournament_id],
+        )
+        .await?
+        .map(|x| x.into());
+    Ok(result)
+}
+
+// inserts a copy of an archived tournament finalization under a new id, keeping its creation time
+pub async fn restore(
+    con: &mut impl GenericClient,
+    tournament_finalization: TournamentFinalization,
+) -> Result<TournamentFinalization, tokio_postgres::Error> {
+    let row = con
+        .query_one(
+            "INSERT INTO
+             tournament_finalization(
+                 creation_time,
+                 creator_user_id,
+                 tournament_id,
+                 n_rounds,
+                 n_matchups
+             )
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING tournament_finalization_id
+            ",
+            &[
+                &tournament_finalization.creation_time,
+                &tournament_finalization.creator_user_id,
+                &tournament_finalization.tournament_id,
+                &tournament_finalization.n_rounds,
+                &tournament_finalization.n_matchups,
+            ],
+        )
+        .await?;
+
+    Ok(TournamentFinalization {
+        tournament_finalization_id: row.get(0),
+        ..tournament_finalization
+    })
+}
+
+pub async fn query(
+    con: &mut impl GenericClient,
+    props: request::TournamentFinalizationViewProps,
+) -> Result<Vec<TournamentFinalization>, tokio_postgres::Error> {
+    let sql = [
+        "SELECT tf.* FROM tournament_finalization tf",
+        " WHERE 1 = 1",
+        " AND ($1::bigint[] IS NULL OR tf.tournament_finalization_id = ANY($1))",
+        " AND ($2::bigint   IS NULL OR tf.creation_time >= $2)",
+        " AND ($3::bigint   IS NULL OR tf.creation_time <= $3)",
+        " AND ($4::bigint[] IS NULL OR tf.creator_user_id = ANY($4))",
+        " AND ($5::bigint[] IS NULL OR tf.tournament_id = ANY($5))",
+        // start after the cursor
+        " AND ($6::bigint   IS NULL OR tf.tournament_finalization_id > $6)",
+        " ORDER BY tf.tournament_finalization_id",
+        " LIMIT $7",
+    ]
+    .join("\n");
+
+    let stmnt = con.prepare(&sql).await?;
+
+    let results = con
+        .query(
+            &stmnt,
+            &[
+                &props.tournament_finalization_id,
+                &props.min_creation_time,
+                &props.max_creation_time,
+                &props.creator_user_id,
+                &props.tournament_id,
+                &props.cursor.as_deref().and_then(utils::decode_cursor),
+                &props.limit,
+            ],
+        )
+        .await?
+        .into_iter()
+        .map(|row| row.into())
+        .collect();
+
+    Ok(results)
+}
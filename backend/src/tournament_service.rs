@@ -48,3 +48,77 @@ pub async fn get_by_tournament_id(
   Ok(result)
 }
 
+// like get_by_tournament_id, but holds the tournament's row lock until the transaction ends
+// so that changes that may only happen once per tournament are serialized
+pub async fn get_by_tournament_id_for_update(
+  con: &mut impl GenericClient,
+  tournament_id: i64,
+) -> Result<Option<Tournament>, tokio_postgres::Error> {
+  let result = con
+    .query_opt("SELECT * FROM tournament WHERE tournament_id=$1 FOR UPDATE", &[&tournament_id])
+    .await?
+    .map(|x| x.into());
+  Ok(result)
+}
+
+// inserts a copy of an archived tournament under a new id, keeping its creation time
+pub async fn restore(
+  con: &mut impl GenericClient,
+  tournament: Tournament,
+) -> Result<Tournament, tokio_postgres::Error> {
+  let row = con
+    .query_one(
+      "INSERT INTO
+       tournament(
+           creation_time,
+           creator_user_id
+       )
+       VALUES($1, $2)
+       RETURNING tournament_id
+      ",
+      &[&tournament.creation_time, &tournament.creator_user_id],
+    )
+    .await?;
+
+  Ok(Tournament {
+    tournament_id: row.get(0),
+    ..tournament
+  })
+}
+
+pub async fn get_by_tournament_ids(
+  con: &mut impl GenericClient,
+  tournament_ids: &[i64],
+) -> Result<Vec<Tournament>, tokio_postgres::Error> {
+  let result = con
+    .query("SELECT * FROM tournament WHERE tournament_id = ANY($1)", &[&tournament_ids])
+    .await?
+    .into_iter()
+    .map(|x| x.into())
+    .collect();
+  Ok(result)
+}
+
+// whether the user created a tournament that both submissions have been entered in,
+// which is where any match between them would have been played
+pub async fn is_creator_of_tournament_with_pair(
+  con: &mut impl GenericClient,
+  creator_user_id: i64,
+  submission_id: i64,
+  opponent_submission_id: i64,
+) -> Result<bool, tokio_postgres::Error> {
+  let sql = [
+    "SELECT EXISTS (",
+    "  SELECT 1 FROM tournament t",
+    "  WHERE t.creator_user_id = $1",
+    "  AND EXISTS (SELECT 1 FROM tournament_submission ts WHERE ts.tournament_id = t.tournament_id AND ts.submission_id = $2)",
+    "  AND EXISTS (SELECT 1 FROM tournament_submission ts WHERE ts.tournament_id = t.tournament_id AND ts.submission_id = $3)",
+    ")",
+  ]
+  .join("\n");
+
+  let row = con
+    .query_one(&sql, &[&creator_user_id, &submission_id, &opponent_submission_id])
+    .await?;
+  Ok(row.get(0))
+}
@@ -2,6 +2,7 @@ use crate::request::TournamentSubmissionKind;
 
 use super::db_types::*;
 use std::convert::From;
+use super::utils;
 use tokio_postgres::GenericClient;
 
 impl From<tokio_postgres::row::Row> for TournamentSubmission {
@@ -15,6 +16,7 @@ impl From<tokio_postgres::row::Row> for TournamentSubmission {
             tournament_id: row.get("tournament_id"),
             name: row.get("name"),
             kind: (row.get::<_, i64>("kind") as u8).try_into().unwrap(),
+            auto_compete: row.get("auto_compete"),
         }
     }
 }
@@ -27,6 +29,7 @@ pub async fn add(
     tournament_id: i64,
     name: String,
     kind: TournamentSubmissionKind,
+    auto_compete: bool,
 ) -> Result<TournamentSubmission, tokio_postgres::Error> {
     let row = con
         .query_one(
@@ -36,9 +39,10 @@ pub async fn add(
                  submission_id,
                  tournament_id,
                  name,
-                 kind
+                 kind,
+                 auto_compete
              )
-             VALUES ($1, $2, $3, $4, $5)
+             VALUES ($1, $2, $3, $4, $5, $6)
              RETURNING tournament_submission_id, creation_time
             ",
             &[
@@ -47,6 +51,7 @@ pub async fn add(
                 &tournament_id,
                 &name,
                 &(kind.clone() as i64),
+                &auto_compete,
             ],
         )
         .await?;
@@ -59,6 +64,76 @@ pub async fn add(
         tournament_id,
         name,
         kind,
+        auto_compete,
+    })
+}
+
+pub async fn get_by_tournament_submission_id(
+    con: &mut impl GenericClient,
+    tournament_submission_id: i64,
+) -> Result<Option<TournamentSubmission>, tokio_postgres::Error> {
+    let result = con
+        .query_opt(
+            "SELECT * FROM tournament_submission WHERE tournament_submission_id=$1",
+            &[&tournament_submission_id],
+        )
+        .await?
+        .map(|x| x.into());
+    Ok(result)
+}
+
+// every entry ever made in the tournament, oldest first
+pub async fn get_by_tournament_id(
+    con: &mut impl GenericClient,
+    tournament_id: i64,
+) -> Result<Vec<TournamentSubmission>, tokio_postgres::Error> {
+    let result = con
+        .query(
+            "SELECT * FROM tournament_submission WHERE tournament_id=$1 ORDER BY tournament_submission_id",
+            &[&tournament_id],
+        )
+        .await?
+        .into_iter()
+        .map(|x| x.into())
+        .collect();
+    Ok(result)
+}
+
+// inserts a copy of an archived tournament submission under a new id, keeping its creation time
+pub async fn restore(
+    con: &mut impl GenericClient,
+    tournament_submission: TournamentSubmission,
+) -> Result<TournamentSubmission, tokio_postgres::Error> {
+    let row = con
+        .query_one(
+            "INSERT INTO
+             tournament_submission(
+                 creation_time,
+                 creator_user_id,
+                 submission_id,
+                 tournament_id,
+                 name,
+                 kind,
+                 auto_compete
+             )
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             RETURNING tournament_submission_id
+            ",
+            &[
+                &tournament_submission.creation_time,
+                &tournament_submission.creator_user_id,
+                &tournament_submission.submission_id,
+                &tournament_submission.tournament_id,
+                &tournament_submission.name,
+                &(tournament_submission.kind.clone() as i64),
+                &tournament_submission.auto_compete,
+            ],
+        )
+        .await?;
+
+    Ok(TournamentSubmission {
+        tournament_submission_id: row.get(0),
+        ..tournament_submission
     })
 }
 
@@ -117,6 +192,141 @@ pub async fn get_recent_by_tournament_submission(
     Ok(results)
 }
 
+// validation entries of these submissions that are waiting to compete automatically
+// validation entries waiting to compete automatically that have the other submission as a testcase,
+// which are the ones that a matchup between the two counts towards
+pub async fn get_recent_auto_compete_by_testcase_pair(
+    con: &mut impl GenericClient,
+    submission_id: i64,
+    opponent_submission_id: i64,
+) -> Result<Vec<TournamentSubmission>, tokio_postgres::Error> {
+    let sql = [
+        "SELECT ts.* FROM recent_tournament_submission ts",
+        " INNER JOIN recent_tournament_submission tc ON tc.tournament_id = ts.tournament_id",
+        " WHERE 1 = 1",
+        " AND (",
+        "   (ts.submission_id = $1 AND tc.submission_id = $2)",
+        "   OR (ts.submission_id = $2 AND tc.submission_id = $1)",
+        " )",
+        " AND ts.kind = $3",
+        " AND ts.auto_compete",
+        " AND tc.kind = $4",
+        " ORDER BY ts.tournament_submission_id",
+    ]
+    .join("\n");
+
+    let stmnt = con.prepare(&sql).await?;
+
+    let results = con
+        .query(
+            &stmnt,
+            &[
+                &submission_id,
+                &opponent_submission_id,
+                &(TournamentSubmissionKind::Validate as i64),
+                &(TournamentSubmissionKind::Testcase as i64),
+            ],
+        )
+        .await?
+        .into_iter()
+        .map(|row| row.into())
+        .collect();
+
+    Ok(results)
+}
+
+// validation entries of this user's that are waiting to compete automatically
+pub async fn get_recent_auto_compete_by_creator_user_id(
+    con: &mut impl GenericClient,
+    creator_user_id: i64,
+) -> Result<Vec<TournamentSubmission>, tokio_postgres::Error> {
+    let sql = [
+        "SELECT ts.* FROM recent_tournament_submission ts",
+        " WHERE 1 = 1",
+        " AND ts.creator_user_id = $1",
+        " AND ts.kind = $2",
+        " AND ts.auto_compete",
+        " ORDER BY ts.tournament_submission_id",
+    ]
+    .join("\n");
+
+    let stmnt = con.prepare(&sql).await?;
+
+    let results = con
+        .query(
+            &stmnt,
+            &[&creator_user_id, &(TournamentSubmissionKind::Validate as i64)],
+        )
+        .await?
+        .into_iter()
+        .map(|row| row.into())
+        .collect();
+
+    Ok(results)
+}
+
+// serializes changes to a submission's entry in a tournament until the transaction ends
+pub async fn lock_tournament_submission(
+    con: &mut impl GenericClient,
+    tournament_id: i64,
+    submission_id: i64,
+) -> Result<(), tokio_postgres::Error> {
+    con.execute(
+        "SELECT pg_advisory_xact_lock($1::int, $2::int)",
+        &[&(tournament_id as i32), &(submission_id as i32)],
+    )
+    .await?;
+    Ok(())
+}
+
+// whether the submission is currently entered in any tournament as one of the kinds
+pub async fn is_recent_by_submission_kind(
+    con: &mut impl GenericClient,
+    submission_id: i64,
+    kind: &[TournamentSubmissionKind],
+) -> Result<bool, tokio_postgres::Error> {
+    let sql = [
+        "SELECT EXISTS (",
+        "  SELECT 1 FROM recent_tournament_submission ts",
+        "  WHERE ts.submission_id = $1",
+        "  AND ts.kind = ANY($2)",
+        ")",
+    ]
+    .join("\n");
+
+    let stmnt = con.prepare(&sql).await?;
+
+    let kinds = kind.iter().map(|x| x.clone() as i64).collect::<Vec<i64>>();
+
+    let row = con.query_one(&stmnt, &[&submission_id, &kinds]).await?;
+
+    Ok(row.get(0))
+}
+
+pub async fn count_recent_by_creator_kind(
+    con: &mut impl GenericClient,
+    tournament_id: i64,
+    creator_user_id: i64,
+    kind: TournamentSubmissionKind,
+) -> Result<i64, tokio_postgres::Error> {
+    let sql = [
+        "SELECT COUNT(*) FROM recent_tournament_submission ts",
+        " WHERE 1 = 1",
+        " AND ts.tournament_id = $1",
+        " AND ts.creator_user_id = $2",
+        " AND ts.kind = $3",
+    ]
+    .join("\n");
+
+    let stmnt = con.prepare(&sql).await?;
+
+    let row = con
+        .query_one(&stmnt, &[&tournament_id, &creator_user_id, &(kind as i64)])
+        .await?;
+
+    Ok(row.get(0))
+}
+
 pub async fn query(
     con: &mut impl GenericClient,
     props: super::request::TournamentSubmissionViewProps,
@@ -137,7 +347,10 @@ pub async fn query(
         " AND ($7::bigint[] IS NULL OR ts.tournament_id = ANY($7))",
         " AND ($8::bigint[] IS NULL OR ts.submission_id = ANY($8))",
         " AND ($9::bigint   IS NULL OR ts.kind = $9)",
+        // start after the cursor
+        " AND ($10::bigint   IS NULL OR ts.tournament_submission_id > $10)",
         " ORDER BY ts.tournament_submission_id",
+        " LIMIT $11",
     ]
     .join("\n");
 
@@ -156,6 +369,8 @@ pub async fn query(
                 &props.tournament_id,
                 &props.submission_id,
                 &props.kind.map(|x| x as i64),
+                &props.cursor.as_deref().and_then(utils::decode_cursor),
+                &props.limit,
             ],
         )
         .await?
@@ -1,8 +1,36 @@
+use base64::Engine;
 use rand::{distributions::Alphanumeric, Rng};
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+// the first max_len bytes of s, cut at a char boundary
+pub fn head(s: &str, max_len: usize) -> String {
+    let mut end = usize::min(s.len(), max_len);
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    s[..end].to_owned()
+}
+
+// the last max_len bytes of s, cut at a char boundary
+pub fn tail(s: &str, max_len: usize) -> String {
+    let mut start = s.len().saturating_sub(max_len);
+    while !s.is_char_boundary(start) {
+        start += 1;
+    }
+    s[start..].to_owned()
+}
+
+// quotes a CSV field if it contains a delimiter, a quote, or a line break (RFC 4180)
+pub fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
 pub fn current_time_millis() -> i64 {
     let since_the_epoch = SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -57,5 +85,73 @@ pub fn random_string() -> String {
         .take(16)
         .map(char::from)
         .collect();
-    return s;
+    s
+}
+
+// creates an opaque pagination cursor pointing just after the given id
+pub fn encode_cursor(id: i64) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(id.to_string())
+}
+
+// returns the id a pagination cursor points after, or None if it is malformed
+pub fn decode_cursor(cursor: &str) -> Option<i64> {
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(cursor)
+        .ok()?;
+    String::from_utf8(bytes).ok()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_round_trips() {
+        for id in [0, 1, 42, -1, i64::MAX, i64::MIN] {
+            assert_eq!(decode_cursor(&encode_cursor(id)), Some(id));
+        }
+    }
+
+    #[test]
+    fn malformed_cursor_is_rejected() {
+        assert_eq!(decode_cursor(""), None);
+        assert_eq!(decode_cursor("not a cursor!"), None);
+        // valid base64, but not a number
+        assert_eq!(
+            decode_cursor(&base64::engine::general_purpose::URL_SAFE_NO_PAD.encode("abc")),
+            None
+        );
+    }
+
+    #[test]
+    fn head_and_tail_keep_short_strings() {
+        assert_eq!(head("abc", 10), "abc");
+        assert_eq!(tail("abc", 10), "abc");
+        assert_eq!(head("abc", 2), "ab");
+        assert_eq!(tail("abc", 2), "bc");
+        assert_eq!(head("abc", 0), "");
+        assert_eq!(tail("abc", 0), "");
+    }
+
+    #[test]
+    fn head_and_tail_cut_at_char_boundaries() {
+        // é is 2 bytes and 😀 is 4
+        let s = "é😀é";
+        assert_eq!(head(s, 1), "");
+        assert_eq!(head(s, 2), "é");
+        assert_eq!(head(s, 5), "é");
+        assert_eq!(head(s, 6), "é😀");
+        assert_eq!(tail(s, 1), "");
+        assert_eq!(tail(s, 2), "é");
+        assert_eq!(tail(s, 5), "é");
+        assert_eq!(tail(s, 6), "😀é");
+    }
+
+    #[test]
+    fn csv_field_quotes_only_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
 }
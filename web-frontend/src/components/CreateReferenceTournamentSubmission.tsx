@@ -0,0 +1,137 @@
+import { ApiKey } from "@innexgo/frontend-auth-api"
+import { Formik, FormikHelpers } from "formik"
+import { Button, Form } from "react-bootstrap"
+import { Strategies, Strategy, strategyNames, TournamentData, TournamentSubmission, tournamentSubmissionNewReference } from "../utils/api"
+import { isErr } from '@innexgo/frontend-common';
+
+type CreateReferenceTournamentSubmissionProps = {
+  tournamentData: TournamentData,
+  apiKey: ApiKey,
+  postSubmit: (ts: TournamentSubmission) => void
+}
+
+// enters one of the built in reference strategies into the tournament
+function CreateReferenceTournamentSubmission(props: CreateReferenceTournamentSubmissionProps) {
+
+  type CreateReferenceValue = {
+    strategy: Strategy,
+    kind: ("COMPETE" | "TESTCASE"),
+    name: string,
+  }
+
+  const onSubmit = async (values: CreateReferenceValue,
+    fprops: FormikHelpers<CreateReferenceValue>) => {
+
+    const maybeTournamentSubmission = await tournamentSubmissionNewReference({
+      tournamentId: props.tournamentData.tournament.tournamentId,
+      strategy: values.strategy,
+      name: values.name === "" ? undefined : values.name,
+      kind: values.kind,
+      apiKey: props.apiKey.key,
+    });
+
+    if (isErr(maybeTournamentSubmission)) {
+      switch (maybeTournamentSubmission.Err) {
+        case "NO_CAPABILITY": {
+          fprops.setStatus({
+            failureResult: "Only the tournament's creator can add reference strategies.",
+            successResult: ""
+          });
+          break;
+        }
+        case "TOURNAMENT_ARCHIVED": {
+          fprops.setStatus({
+            failureResult: "This tournament has been archived",
+            successResult: ""
+          });
+          break;
+        }
+        case "TOURNAMENT_FINALIZED": {
+          fprops.setStatus({
+            failureResult: "This tournament has been finalized",
+            successResult: ""
+          });
+          break;
+        }
+        default: {
+          fprops.setStatus({
+            failureResult: "An unknown or network error has occured while trying to add the reference strategy.",
+            successResult: ""
+          });
+          break;
+        }
+      }
+      return;
+    }
+
+    fprops.setStatus({
+      failureResult: "",
+      successResult: "Reference Strategy Added"
+    });
+    // execute callback
+    props.postSubmit(maybeTournamentSubmission.Ok);
+  }
+
+  return <>
+    <Formik<CreateReferenceValue>
+      onSubmit={onSubmit}
+      initialValues={{
+        strategy: "TIT_FOR_TAT",
+        kind: "COMPETE",
+        name: "",
+      }}
+      initialStatus={{
+        failureResult: "",
+        successResult: ""
+      }}
+    >
+      {(fprops) => <>
+        <Form
+          noValidate
+          onSubmit={fprops.handleSubmit} >
+          <div hidden={fprops.status.successResult !== ""}>
+            <Form.Group className="mb-3">
+              <Form.Label>Strategy</Form.Label>
+              <Form.Select
+                name="strategy"
+                value={fprops.values.strategy}
+                onChange={e => fprops.setFieldValue("strategy", e.target.value)}
+              >
+                {Strategies.map(s => <option key={s} value={s}>{strategyNames[s]}</option>)}
+              </Form.Select>
+            </Form.Group>
+            <Form.Group className="mb-3">
+              <Form.Label>Enter As</Form.Label>
+              <Form.Select
+                name="kind"
+                value={fprops.values.kind}
+                onChange={e => fprops.setFieldValue("kind", e.target.value)}
+              >
+                <option value="COMPETE">Baseline Competitor</option>
+                <option value="TESTCASE">Testcase</option>
+              </Form.Select>
+            </Form.Group>
+            <Form.Group className="mb-3">
+              <Form.Label>Entry Name</Form.Label>
+              <Form.Control
+                name="name"
+                type="text"
+                placeholder={strategyNames[fprops.values.strategy]}
+                as="input"
+                value={fprops.values.name}
+                onChange={e => fprops.setFieldValue("name", e.target.value)}
+              />
+            </Form.Group>
+            <Form.Group className="mb-3">
+              <Button type="submit">Submit Form</Button>
+            </Form.Group>
+            <Form.Text className="text-danger">{fprops.status.failureResult}</Form.Text>
+          </div>
+          <Form.Text className="text-success">{fprops.status.successResult}</Form.Text>
+        </Form>
+      </>}
+    </Formik>
+  </>
+}
+
+export default CreateReferenceTournamentSubmission;
@@ -3,6 +3,7 @@ import { Formik, FormikHelpers, FormikErrors } from 'formik'
 import { Button, Form } from "react-bootstrap";
 import { TournamentData, tournamentNew } from "../utils/api";
 import { isErr } from '@innexgo/frontend-common';
+import ChipInput from './ChipInput';
 import { ApiKey } from '@innexgo/frontend-auth-api';
 import { AuthenticatedComponentProps } from '@innexgo/auth-react-components';
 
@@ -19,6 +20,7 @@ function CreateTournament(props: CreateTournamentProps) {
     description: string,
     nRounds: string,
     nMatchups: string,
+    disallowedImports: string[],
   }
 
   const onSubmit = async (values: CreateTournamentValue,
@@ -61,6 +63,7 @@ function CreateTournament(props: CreateTournamentProps) {
       description: values.description,
       nMatchups,
       nRounds,
+      disallowedImports: values.disallowedImports,
       apiKey: props.apiKey.key,
     });
 
@@ -94,6 +97,13 @@ function CreateTournament(props: CreateTournamentProps) {
           });
           break;
         }
+        case "TOURNAMENT_DATA_DISALLOWED_IMPORT_INVALID": {
+          fprops.setStatus({
+            failureResult: "A disallowed import isn't a module name",
+            successResult: ""
+          });
+          break;
+        }
         default: {
           fprops.setStatus({
             failureResult: "An unknown or network error has occured while trying to create tournament.",
@@ -121,6 +131,7 @@ function CreateTournament(props: CreateTournamentProps) {
         description: "",
         nRounds: "" + 10,
         nMatchups: "" + 10,
+        disallowedImports: [],
       }}
       initialStatus={{
         failureResult: "",
@@ -187,6 +198,21 @@ function CreateTournament(props: CreateTournamentProps) {
                 Using more than 100 matches total is not reccomended.
               </Form.Label>
             </Form.Group>
+            <Form.Group className="mb-3">
+              <Form.Label>Disallowed Imports</Form.Label>
+              <ChipInput
+                placeholder="Module name, such as os"
+                chips={fprops.values.disallowedImports}
+                onSubmit={(value: string) => {
+                  const module = value.trim();
+                  if (module !== "" && !fprops.values.disallowedImports.includes(module)) {
+                    fprops.setFieldValue("disallowedImports", [...fprops.values.disallowedImports, module]);
+                  }
+                }}
+                onRemove={(index: number) => fprops.setFieldValue("disallowedImports", fprops.values.disallowedImports.filter((_, i) => i !== index))}
+              />
+              <Form.Text>Entries that import these modules, or their submodules, are rejected.</Form.Text>
+            </Form.Group>
             <Form.Group className="mb-3">
               <Button type="submit">Submit Form</Button>
             </Form.Group>
@@ -22,6 +22,7 @@ function CreateTournamentSubmission(props: CreateTournamentSubmissionProps) {
 
   type CreateSubmissionValue = {
     name: string,
+    autoCompete: boolean,
   }
 
   const onSubmit = async (values: CreateSubmissionValue,
@@ -57,6 +58,20 @@ function CreateTournamentSubmission(props: CreateTournamentSubmissionProps) {
           });
           break;
         }
+        case "SUBMISSION_SYNTAX_ERROR": {
+          fprops.setStatus({
+            failureResult: "Your code has a syntax error.",
+            successResult: ""
+          });
+          break;
+        }
+        case "SUBMISSION_SHOULD_DEFECT_INVALID": {
+          fprops.setStatus({
+            failureResult: "Your code must define should_defect(opp_defection_function, opponent_defection_history) as a regular function taking 2 arguments.",
+            successResult: ""
+          });
+          break;
+        }
         default: {
           fprops.setStatus({
             failureResult: "An unknown or network error has occured while trying to create submission.",
@@ -74,6 +89,7 @@ function CreateTournamentSubmission(props: CreateTournamentSubmissionProps) {
       submissionId: maybeSubmission.Ok.submissionId,
       name: values.name,
       kind: props.kind,
+      autoCompete: props.kind === "VALIDATE" && values.autoCompete,
       apiKey: props.apiKey.key,
     });
 
@@ -87,6 +103,13 @@ function CreateTournamentSubmission(props: CreateTournamentSubmissionProps) {
           });
           break;
         }
+        case "SUBMISSION_IMPORT_DISALLOWED": {
+          fprops.setStatus({
+            failureResult: `Your code imports a module this tournament disallows: ${props.tournamentData.disallowedImports.join(", ")}`,
+            successResult: ""
+          });
+          break;
+        }
         default: {
           fprops.setStatus({
             failureResult: "An unknown or network error has occured while trying to create submission.",
@@ -111,6 +134,7 @@ function CreateTournamentSubmission(props: CreateTournamentSubmissionProps) {
       onSubmit={onSubmit}
       initialValues={{
         name: "",
+        autoCompete: true,
       }}
       initialStatus={{
         failureResult: "",
@@ -142,6 +166,14 @@ function CreateTournamentSubmission(props: CreateTournamentSubmissionProps) {
               />
               <Form.Control.Feedback type="invalid">{fprops.errors.name}</Form.Control.Feedback>
             </Form.Group>
+            <Form.Group className="mb-3" hidden={props.kind !== "VALIDATE"}>
+              <Form.Check
+                name="autoCompete"
+                label="Enter the tournament automatically once all testcases pass"
+                checked={fprops.values.autoCompete}
+                onChange={e => fprops.setFieldValue("autoCompete", e.target.checked)}
+              />
+            </Form.Group>
             <Form.Group className="mb-3">
               <Button type="submit">Submit Form</Button>
             </Form.Group>
@@ -1,4 +1,5 @@
-import { MatchResolution, MatchResolutionLite, Tournament, TournamentData, TournamentSubmission, TournamentSubmissionKind } from "../utils/api"
+import { Badge } from "react-bootstrap";
+import { MatchResolution, MatchResolutionLite, StreamMessage, strategyNames, Tournament, TournamentData, TournamentSubmission, TournamentSubmissionKind } from "../utils/api"
 import { scorePrisonersDilemma } from "../utils/scoring";
 
 import update from 'immutability-helper';
@@ -26,8 +27,13 @@ export const tournamentSubmissionColors = new Map<TournamentSubmissionKind, stri
 
 export function lookupTableWebsocketGenerator(setLookupTable: (f: ((l: LookupTable) => LookupTable)) => void) {
   return function(msg: MessageEvent<string>) {
+    const message: StreamMessage<MatchResolutionLite> = JSON.parse(msg.data);
+    // control messages don't change the table
+    if (message.kind !== "ITEM") {
+      return;
+    }
     // get match resolution lite
-    const mrl: MatchResolutionLite = JSON.parse(msg.data);
+    const mrl = message.item;
     // set lookup table with data given
     setLookupTable(state =>
       update(state, {
@@ -188,6 +194,9 @@ function CrossTable(props: CrossTableProps) {
           <a href={`/tournament_submission?tournamentId=${rowSubmission.tournament.tournamentId}&submissionId=${rowSubmission.submissionId}`}>{
             rowSubmission.name
           }</a>
+          {rowSubmission.referenceStrategy === null ? null :
+            <Badge bg="secondary" className="ms-1" title={strategyNames[rowSubmission.referenceStrategy]}>Reference</Badge>
+          }
         </th>
         {row.map((x, i) =>
           <td
@@ -9,6 +9,7 @@ import { Formik, FormikErrors, FormikHelpers } from 'formik'
 import format from 'date-fns/format';
 
 import { isErr, unwrap } from '@innexgo/frontend-common';
+import ChipInput from './ChipInput';
 import { User, ApiKey } from '@innexgo/frontend-auth-api';
 import { useInRouterContext } from 'react-router-dom';
 
@@ -26,6 +27,7 @@ function EditTournamentData(props: EditTournamentDataProps) {
     description: string,
     nRounds: string,
     nMatchups: string,
+    disallowedImports: string[],
   }
 
   const onSubmit = async (values: EditTournamentDataValue,
@@ -66,6 +68,7 @@ function EditTournamentData(props: EditTournamentDataProps) {
       nRounds,
       nMatchups,
       active: props.tournamentData.active,
+      disallowedImports: values.disallowedImports,
     });
 
     if (isErr(maybeTournamentData)) {
@@ -98,6 +101,13 @@ function EditTournamentData(props: EditTournamentDataProps) {
           });
           break;
         }
+        case "TOURNAMENT_DATA_DISALLOWED_IMPORT_INVALID": {
+          fprops.setStatus({
+            failureResult: "A disallowed import isn't a module name",
+            successResult: ""
+          });
+          break;
+        }
         default: {
           fprops.setStatus({
             failureResult: "An unknown or network error has occured while modifying tournament data.",
@@ -126,6 +136,7 @@ function EditTournamentData(props: EditTournamentDataProps) {
         description: props.tournamentData.description,
         nMatchups: "" + props.tournamentData.nMatchups,
         nRounds: "" + props.tournamentData.nRounds,
+        disallowedImports: props.tournamentData.disallowedImports,
       }}
       initialStatus={{
         failureResult: "",
@@ -191,6 +202,21 @@ function EditTournamentData(props: EditTournamentDataProps) {
                 Using more than 100 matches total is not reccomended.
               </Form.Label>
             </Form.Group>
+            <Form.Group className="mb-3">
+              <Form.Label>Disallowed Imports</Form.Label>
+              <ChipInput
+                placeholder="Module name, such as os"
+                chips={fprops.values.disallowedImports}
+                onSubmit={(value: string) => {
+                  const module = value.trim();
+                  if (module !== "" && !fprops.values.disallowedImports.includes(module)) {
+                    fprops.setFieldValue("disallowedImports", [...fprops.values.disallowedImports, module]);
+                  }
+                }}
+                onRemove={(index: number) => fprops.setFieldValue("disallowedImports", fprops.values.disallowedImports.filter((_, i) => i !== index))}
+              />
+              <Form.Text>Entries that import these modules, or their submodules, are rejected.</Form.Text>
+            </Form.Group>
             <Form.Group className="mb-3">
               <Button type="submit">Submit</Button>
             </Form.Group>
@@ -316,6 +342,10 @@ const ManageTournamentData = (props: {
           <th>Number of Rounds per Matchup</th>
           <td>{props.tournamentData.nRounds}</td>
         </tr>
+        <tr>
+          <th>Disallowed Imports</th>
+          <td>{props.tournamentData.disallowedImports.length === 0 ? "None" : props.tournamentData.disallowedImports.join(", ")}</td>
+        </tr>
         <tr>
           <th>Creator</th>
           <td><ViewUser userId={props.tournamentData.tournament.creatorUserId} apiKey={props.apiKey} expanded={false} /></td>
@@ -2,7 +2,7 @@ import React from 'react';
 import { Table } from 'react-bootstrap';
 import { Action, DisplayModal, Link } from '@innexgo/common-react-components';
 import { Eye as ViewIcon, Pencil as EditIcon, X as DeleteIcon, } from 'react-bootstrap-icons';
-import { TournamentData, TournamentSubmission, TournamentSubmissionKind } from '../utils/api';
+import { strategyNames, TournamentData, TournamentSubmission, TournamentSubmissionKind } from '../utils/api';
 import format from 'date-fns/format';
 
 import ArchiveTournamentSubmission from '../components/ArchiveTournamentSubmission';
@@ -40,6 +40,13 @@ const ManageTournamentSubmission = (props: {
             />
           </td>
         </tr>
+        <tr hidden={props.tournamentSubmission.referenceStrategy === null}>
+          <th>Reference Strategy</th>
+          <td>
+            {props.tournamentSubmission.referenceStrategy === null ? null :
+              strategyNames[props.tournamentSubmission.referenceStrategy]}
+          </td>
+        </tr>
         <tr>
           <th>Creation Time</th>
           <td>{format(props.tournamentData.tournament.creationTime, "MMM do, hh:mm")} </td>
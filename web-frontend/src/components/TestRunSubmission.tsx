@@ -0,0 +1,145 @@
+import React from 'react';
+import { ApiKey } from "@innexgo/frontend-auth-api"
+import { Formik, FormikErrors, FormikHelpers } from "formik"
+import { Button, Form, Table } from "react-bootstrap"
+import { Strategies, Strategy, strategyNames, SubmissionTestRun, submissionTestRun } from "../utils/api"
+import { isErr } from '@innexgo/frontend-common';
+
+type TestRunSubmissionProps = {
+  code: string,
+  apiKey: ApiKey,
+}
+
+const showMove = (defected: boolean | null) =>
+  defected === null ? "Failed" : defected ? "Defect" : "Cooperate";
+
+// plays the code in the editor against a well known strategy, without submitting it
+function TestRunSubmission(props: TestRunSubmissionProps) {
+  const [testRun, setTestRun] = React.useState<SubmissionTestRun | null>(null);
+
+  type TestRunValue = {
+    opponentStrategy: Strategy,
+    nRounds: string,
+  }
+
+  const onSubmit = async (values: TestRunValue,
+    fprops: FormikHelpers<TestRunValue>) => {
+
+    let errors: FormikErrors<TestRunValue> = {};
+
+    // Validate input
+    let hasError = false;
+
+    const nRounds = parseInt(values.nRounds);
+    if (isNaN(nRounds) || nRounds < 1 || nRounds > 20) {
+      errors.nRounds = "Please enter a number of rounds between 1 and 20.";
+      hasError = true;
+    }
+
+    fprops.setErrors(errors);
+    if (hasError) {
+      return;
+    }
+
+    const maybeTestRun = await submissionTestRun({
+      code: props.code,
+      opponentStrategy: values.opponentStrategy,
+      nRounds,
+      apiKey: props.apiKey.key,
+    });
+
+    if (isErr(maybeTestRun)) {
+      switch (maybeTestRun.Err) {
+        case "SUBMISSION_TOO_LONG": {
+          fprops.setStatus({ failureResult: "Your code is too long." });
+          break;
+        }
+        case "RATE_LIMITED": {
+          fprops.setStatus({ failureResult: "You've run too many tests recently, try again in a bit." });
+          break;
+        }
+        default: {
+          fprops.setStatus({ failureResult: "An unknown or network error has occured while trying to run your code." });
+          break;
+        }
+      }
+      return;
+    }
+
+    fprops.setStatus({ failureResult: "" });
+    setTestRun(maybeTestRun.Ok);
+  }
+
+  return <>
+    <Formik<TestRunValue>
+      onSubmit={onSubmit}
+      initialValues={{
+        opponentStrategy: "TIT_FOR_TAT",
+        nRounds: "5",
+      }}
+      initialStatus={{
+        failureResult: "",
+      }}
+    >
+      {(fprops) => <>
+        <Form
+          noValidate
+          onSubmit={fprops.handleSubmit} >
+          <Form.Group className="mb-3">
+            <Form.Label>Opponent</Form.Label>
+            <Form.Select
+              name="opponentStrategy"
+              value={fprops.values.opponentStrategy}
+              onChange={e => fprops.setFieldValue("opponentStrategy", e.target.value)}
+            >
+              {Strategies.map(s => <option key={s} value={s}>{strategyNames[s]}</option>)}
+            </Form.Select>
+          </Form.Group>
+          <Form.Group className="mb-3">
+            <Form.Label>Rounds</Form.Label>
+            <Form.Control
+              name="nRounds"
+              type="number"
+              as="input"
+              value={fprops.values.nRounds}
+              onChange={e => fprops.setFieldValue("nRounds", e.target.value)}
+              isInvalid={!!fprops.errors.nRounds}
+            />
+            <Form.Control.Feedback type="invalid">{fprops.errors.nRounds}</Form.Control.Feedback>
+          </Form.Group>
+          <Form.Group className="mb-3">
+            <Button type="submit" disabled={fprops.isSubmitting}>Run</Button>
+          </Form.Group>
+          <Form.Text className="text-danger">{fprops.status.failureResult}</Form.Text>
+        </Form>
+      </>}
+    </Formik>
+    {testRun === null ? null :
+      <Table size="sm">
+        <thead>
+          <tr>
+            <th>Round</th>
+            <th>You</th>
+            <th>Opponent</th>
+            <th>Output</th>
+          </tr>
+        </thead>
+        <tbody>
+          {testRun.rounds.map(r =>
+            <tr key={r.round}>
+              <td>{r.round}</td>
+              <td>{showMove(r.defected)} {r.payoff === null ? null : `(${r.payoff})`}</td>
+              <td>{showMove(r.opponentDefected)} {r.opponentPayoff === null ? null : `(${r.opponentPayoff})`}</td>
+              <td>
+                <pre className="mb-0">{r.stdout}{r.stdoutTruncated ? "\n[truncated]" : ""}</pre>
+                <pre className="mb-0 text-danger">{r.stderrTruncated ? "[truncated]\n" : ""}{r.stderr}</pre>
+              </td>
+            </tr>
+          )}
+        </tbody>
+      </Table>
+    }
+  </>
+}
+
+export default TestRunSubmission;
@@ -8,6 +8,7 @@ import ExternalLayout from '../components/ExternalLayout';
 import { getFirstOr, unwrap } from '@innexgo/frontend-common';
 import AuthenticatedComponentProps from '@innexgo/auth-react-components/lib/components/AuthenticatedComponentProps';
 import CreateTournamentSubmission from '../components/CreateTournamentSubmission';
+import TestRunSubmission from '../components/TestRunSubmission';
 import { DefaultSidebarLayout } from '@innexgo/auth-react-components';
 import DashboardLayout from '../components/DashboardLayout';
 import PythonEditor from '../components/PythonEditor';
@@ -32,6 +33,7 @@ type InnerCompetePageProps = {
 function InnerCompetePage(props: InnerCompetePageProps) {
   const [code, setCode] = React.useState("");
   const [showSubmitModal, setShowSubmitModal] = React.useState(false);
+  const [showTestRunModal, setShowTestRunModal] = React.useState(false);
   const navigate = useNavigate();
 
   const title = props.kind === "VALIDATE"
@@ -54,6 +56,27 @@ function InnerCompetePage(props: InnerCompetePageProps) {
     >
       Submit
     </button>
+    <button
+      style={{
+        position: "absolute",
+        bottom: "2rem",
+        left: "8rem"
+      }}
+      className='btn btn-secondary'
+      onClick={() => setShowTestRunModal(true)}
+    >
+      Run
+    </button>
+    <DisplayModal
+      title="Test Run"
+      show={showTestRunModal}
+      onClose={() => setShowTestRunModal(false)}
+    >
+      <TestRunSubmission
+        code={code}
+        apiKey={props.apiKey}
+      />
+    </DisplayModal>
     <DisplayModal
       title={title}
       show={showSubmitModal}
@@ -81,6 +104,7 @@ const loadCompetePageData = async (props: AsyncProps<CompetePageData>): Promise<
     apiKey: props.apiKey.key
   })
     .then(unwrap)
+    .then(x => x.items)
     .then(x => getFirstOr(x, "NOT_FOUND"))
     .then(unwrap);
 
@@ -25,7 +25,8 @@ const loadData = async (props: AsyncProps<Data>) => {
       onlyRecent: true,
       apiKey: props.apiKey.key
     })
-      .then(unwrap);
+      .then(unwrap)
+      .then(x => x.items);
 
   return {
     tournamentData,
@@ -18,6 +18,7 @@ const Help = (props: AuthenticatedComponentProps) =>
 		<p>To submit a bot to the tournament, open the tournament from the dashboard, then click the <b>Compete!</b> button. You will then be taken to a code editor.</p>
 		<p>Bots are written in Python. Your bot will need to define the function <code>should_defect</code>. Its first argument is your opponent's <code>should_defect</code> function, and its second argument is a list of booleans representing whether your opponent defected in each previous round (earlier rounds are first). <code>should_defect</code> will be run each round; if the function returns <code>True</code>, your bot will defect, and if it returns <code>False</code> it will cooperate.</p>
 		<p><code>should_defect</code> must return within one second. Your bot has filesystem access, but files don't persist across rounds. Packages can't be installed in advance.</p>
+		<p>Python's <code>random</code> is seeded before your bot is loaded, with a seed that depends only on the tournament, the matchup, the round and the two bots, so a round played again makes the same random choices. The seed is available as <code>pdarena.seed</code> after <code>import pdarena</code>, if you want to seed another generator with it.</p>
 
 		<h4>Validation</h4>
 		<p>Once you submit your bot, it will be listed on the leaderboard, and its status will be <b>VALIDATE</b>. This means that your bot is being matched with testcases; during matches with testcases, if your bot errors out or otherwise fails to respond, you won't be able to submit it to the tournament.</p>
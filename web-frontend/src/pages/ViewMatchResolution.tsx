@@ -7,12 +7,12 @@ import ErrorMessage from '../components/ErrorMessage';
 
 import update from 'immutability-helper';
 
-import { unwrap, getFirstOr } from '@innexgo/frontend-common';
+import { unwrap, getFirstOr, isErr } from '@innexgo/frontend-common';
 
 import format from "date-fns/format";
 
 import { Async, AsyncProps } from 'react-async';
-import { MatchResolution, matchResolutionView, Submission, submissionView, TournamentData, tournamentDataView, TournamentSubmission, TournamentSubmissionKind, tournamentSubmissionView } from '../utils/api';
+import { MatchResolution, MatchResolutionOutput, matchResolutionOutput, matchResolutionView, Submission, submissionView, TournamentData, tournamentDataView, TournamentSubmission, TournamentSubmissionKind, tournamentSubmissionView } from '../utils/api';
 import { ApiKey } from '@innexgo/frontend-auth-api';
 import { AuthenticatedComponentProps } from '@innexgo/auth-react-components';
 
@@ -23,6 +23,8 @@ import ManageMatchResolution from '../components/ManageMatchResolution';
 
 type ManageMatchResolutionPageData = {
   matchResolution: MatchResolution,
+  // null if it has been pruned
+  matchResolutionOutput: MatchResolutionOutput | null,
   tournamentData: TournamentData,
   tournamentSubmission: TournamentSubmission,
   opponentTournamentSubmission: TournamentSubmission,
@@ -36,15 +38,27 @@ const loadManageMatchResolutionPage = async (props: AsyncProps<ManageMatchResolu
     apiKey: props.apiKey.key
   })
     .then(unwrap)
+    .then(x => x.items)
     .then(x => getFirstOr(x, "NOT_FOUND"))
     .then(unwrap);
 
+  const maybeMatchResolutionOutput = await matchResolutionOutput({
+    matchResolutionId: props.matchResolutionId,
+    apiKey: props.apiKey.key
+  });
+
+  const matchResolutionOutputOrPruned =
+    isErr(maybeMatchResolutionOutput) && maybeMatchResolutionOutput.Err === "MATCH_RESOLUTION_OUTPUT_PRUNED"
+      ? null
+      : unwrap(maybeMatchResolutionOutput);
+
   const tournamentData = await tournamentDataView({
     tournamentId: [props.tournamentId],
     onlyRecent: true,
     apiKey: props.apiKey.key
   })
     .then(unwrap)
+    .then(x => x.items)
     .then(x => getFirstOr(x, "NOT_FOUND"))
     .then(unwrap);
 
@@ -55,6 +69,7 @@ const loadManageMatchResolutionPage = async (props: AsyncProps<ManageMatchResolu
     apiKey: props.apiKey.key
   })
     .then(unwrap)
+    .then(x => x.items)
     .then(x => getFirstOr(x, "NOT_FOUND"))
     .then(unwrap);
 
@@ -65,12 +80,14 @@ const loadManageMatchResolutionPage = async (props: AsyncProps<ManageMatchResolu
     apiKey: props.apiKey.key
   })
     .then(unwrap)
+    .then(x => x.items)
     .then(x => getFirstOr(x, "NOT_FOUND"))
     .then(unwrap);
 
 
   return {
     matchResolution,
+    matchResolutionOutput: matchResolutionOutputOrPruned,
     tournamentData,
     tournamentSubmission,
     opponentTournamentSubmission,
@@ -105,22 +122,28 @@ function ManageMatchResolutionPage(props: AuthenticatedComponentProps) {
                 </div>
               </Section>
               <Section name="Logs" id="logs">
-                <div>
-                  <h6>Submission Stdout</h6>
-                  <div style={{ width: "100%", overflow: "scroll" }}>
-                    <SyntaxHighligher
-                      showLineNumbers
-                      style={a11yDark}
-                      children={data.matchResolution.stdout} />
-                  </div>
-                  <h6>Submission Stderr</h6>
-                  <div style={{ width: "100%", overflow: "scroll" }}>
-                    <SyntaxHighligher
-                      showLineNumbers
-                      style={a11yDark}
-                      children={data.matchResolution.stderr} />
-                  </div>
-                </div>
+                {data.matchResolutionOutput === null
+                  ? <Form.Text>The logs of this match are no longer kept, since it was replayed or its submission was cancelled.</Form.Text>
+                  : <div>
+                    <h6>Submission Stdout</h6>
+                    <div style={{ width: "100%", overflow: "scroll" }}>
+                      <SyntaxHighligher
+                        showLineNumbers
+                        style={a11yDark}
+                        children={data.matchResolutionOutput.stdout} />
+                    </div>
+                    {data.matchResolutionOutput.stdoutTruncated &&
+                      <Form.Text>Output after this point was cut off.</Form.Text>}
+                    <h6>Submission Stderr</h6>
+                    {data.matchResolutionOutput.stderrTruncated &&
+                      <Form.Text>Output before this point was cut off.</Form.Text>}
+                    <div style={{ width: "100%", overflow: "scroll" }}>
+                      <SyntaxHighligher
+                        showLineNumbers
+                        style={a11yDark}
+                        children={data.matchResolutionOutput.stderr} />
+                    </div>
+                  </div>}
               </Section>
             </>}
             </Async.Fulfilled>
@@ -1,6 +1,6 @@
 import { Alert, Button, Card, Container, Form, Spinner, Table } from 'react-bootstrap';
 import DashboardLayout from '../components/DashboardLayout';
-import { WidgetWrapper, Link, Section } from '@innexgo/common-react-components';
+import { WidgetWrapper, Link, Section, DisplayModal } from '@innexgo/common-react-components';
 import ManageTournamentData from '../components/ManageTournamentData';
 import ErrorMessage from '../components/ErrorMessage';
 
@@ -15,6 +15,7 @@ import { MatchResolution, MatchResolutionLite, matchResolutionLiteStream, matchR
 import { ApiKey } from '@innexgo/frontend-auth-api';
 import { AuthenticatedComponentProps } from '@innexgo/auth-react-components';
 import ManageTournamentSubmissionsTournament from '../components/ManageTournamentSubmissionTournament';
+import CreateReferenceTournamentSubmission from '../components/CreateReferenceTournamentSubmission';
 import CrossTable, { LookupTable, lookupTableWebsocketGenerator } from '../components/CrossTable';
 import React from 'react';
 
@@ -30,6 +31,7 @@ const loadManageTournamentPage = async (props: AsyncProps<ManageTournamentPageDa
     apiKey: props.apiKey.key
   })
     .then(unwrap)
+    .then(x => x.items)
     .then(x => getFirstOr(x, "NOT_FOUND"))
     .then(unwrap);
 
@@ -38,7 +40,8 @@ const loadManageTournamentPage = async (props: AsyncProps<ManageTournamentPageDa
     onlyRecent: true,
     apiKey: props.apiKey.key
   })
-    .then(unwrap);
+    .then(unwrap)
+    .then(x => x.items);
 
   return {
     tournamentData,
@@ -61,6 +64,8 @@ function ManageTournamentPageInner(props: {
 
   const [lookupTable, setLookupTable] = React.useState<LookupTable>([]);
 
+  const [showCreateReference, setShowCreateReference] = React.useState(false);
+
   if (ws === undefined) {
     const new_ws = matchResolutionLiteStream({
       submissionId: props.tournamentSubmissions.map(x => x.submissionId),
@@ -133,7 +138,24 @@ function ManageTournamentPageInner(props: {
       <a className="btn btn-primary mx-3" href={`/compete?tournamentId=${props.tournamentData.tournament.tournamentId}&kind=TESTCASE`} hidden={props.apiKey.creatorUserId !== props.tournamentData.tournament.creatorUserId}>
         Write a Testcase!
       </a>
+      <Button className="mx-3" onClick={() => setShowCreateReference(true)} hidden={props.apiKey.creatorUserId !== props.tournamentData.tournament.creatorUserId}>
+        Add a Reference Strategy
+      </Button>
     </div>
+    <DisplayModal
+      title="Add Reference Strategy"
+      show={showCreateReference}
+      onClose={() => setShowCreateReference(false)}
+    >
+      <CreateReferenceTournamentSubmission
+        tournamentData={props.tournamentData}
+        apiKey={props.apiKey}
+        postSubmit={ts => props.setTournamentSubmissions([
+          ...props.tournamentSubmissions.filter(x => x.submissionId !== ts.submissionId),
+          ts
+        ])}
+      />
+    </DisplayModal>
   </>
 }
 
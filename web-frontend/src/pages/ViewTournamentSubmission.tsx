@@ -52,7 +52,8 @@ const loadManageTournamentSubmissionPage = async (props: AsyncProps<ManageTourna
     onlyRecent: true,
     apiKey: props.apiKey.key
   })
-    .then(unwrap);
+    .then(unwrap)
+    .then(x => x.items);
 
   const tournamentSubmission = tournamentSubmissions.find(x => x.submissionId === props.submissionId);
   if (tournamentSubmission === undefined) {
@@ -66,6 +67,7 @@ const loadManageTournamentSubmissionPage = async (props: AsyncProps<ManageTourna
     apiKey: props.apiKey.key
   })
     .then(unwrap)
+    .then(x => x.items)
     .then(x => getFirstOr(x, "NOT_FOUND"))
     .then(unwrap);
 
@@ -74,6 +76,7 @@ const loadManageTournamentSubmissionPage = async (props: AsyncProps<ManageTourna
     apiKey: props.apiKey.key
   })
     .then(unwrap)
+    .then(x => x.items)
     .then(x => x[0])
 
 
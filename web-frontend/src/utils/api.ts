@@ -6,6 +6,11 @@ export type TournamentSubmissionKind =
   "TESTCASE" |
   "CANCEL";
 
+export type Page<T> = {
+  items: T[],
+  nextCursor: string | null,
+}
+
 export type Submission = {
   submissionId: number,
   creationTime: number,
@@ -29,6 +34,8 @@ export type TournamentData = {
   nRounds: number,
   nMatchups: number,
   active: boolean,
+  // modules that entries may not import, submodules included
+  disallowedImports: string[],
 }
 
 export type TournamentSubmission = {
@@ -39,6 +46,9 @@ export type TournamentSubmission = {
   submissionId: number,
   name: string,
   kind: TournamentSubmissionKind,
+  autoCompete: boolean,
+  // the strategy played, if the entry is a reference submission
+  referenceStrategy: Strategy | null,
 }
 
 export type MatchResolution = {
@@ -49,10 +59,23 @@ export type MatchResolution = {
   round: number,
   matchup: number,
   defected: boolean | null,
+  seed: number | null,
+}
+
+export type MatchResolutionOutput = {
+  matchResolutionId: number,
   stdout: string,
+  stdoutTruncated: boolean,
   stderr: string,
+  stderrTruncated: boolean,
 }
 
+export type StreamMessage<T> =
+  { kind: "ITEM", item: T } |
+  { kind: "SNAPSHOT_COMPLETE" } |
+  { kind: "RESYNC" } |
+  { kind: "HEARTBEAT" };
+
 export type MatchResolutionLite = {
   matchResolutionId: number,
   creationTime: number,
@@ -63,20 +86,50 @@ export type MatchResolutionLite = {
   defected: boolean | null,
 }
 
+export type Standing = {
+  tournamentSubmissionId: number,
+  submissionId: number,
+  name: string,
+  referenceStrategy: Strategy | null,
+  score: number | null,
+  scoreDelta: number | null,
+  rank: number,
+  previousRank: number | null,
+  nScoredRounds: number,
+  withdrawn: boolean,
+}
+
 
 export const AppErrorCodes = [
   "NO_CAPABILITY",
   "SUBMISSION_NONEXISTENT",
   "TOURNAMENT_NONEXISTENT",
+  "TOURNAMENT_FINALIZATION_NONEXISTENT",
+  "MATCH_RESOLUTION_NONEXISTENT",
+  "MATCH_RESOLUTION_OUTPUT_PRUNED",
   "TOURNAMENT_DATA_N_ROUNDS_INVALID",
   "TOURNAMENT_DATA_N_MATCHUPS_INVALID",
   "TOURNAMENT_DATA_TOO_MANY_MATCHES",
+  "TOURNAMENT_DATA_DISALLOWED_IMPORT_INVALID",
   "SUBMISSION_TOO_LONG",
+  "SUBMISSION_SYNTAX_ERROR",
+  "SUBMISSION_SHOULD_DEFECT_INVALID",
+  "SUBMISSION_IMPORT_DISALLOWED",
+  "SUBMISSION_RATE_LIMITED",
+  "TEST_RUN_N_ROUNDS_INVALID",
+  "TEST_RUN_OPPONENT_INVALID",
+  "TOURNAMENT_SUBMISSION_COMPETE_LIMIT",
+  "MATCH_BUDGET_EXCEEDED",
+  "RATE_LIMITED",
   "TOURNAMENT_SUBMISSION_NOT_VALIDATED",
   "TOURNAMENT_SUBMISSION_TESTCASE_INCOMPLETE",
   "TOURNAMENT_SUBMISSION_TESTCASE_FAILS",
   "TOURNAMENT_ARCHIVED",
+  "TOURNAMENT_FINALIZED",
+  "ARCHIVE_VERSION_UNSUPPORTED",
+  "ARCHIVE_INVALID",
   "STREAM_END_BEFORE_REQUEST",
+  "CURSOR_INVALID",
   "DECODE_ERROR",
   "METHOD_NOT_ALLOWED",
   "INTERNAL_SERVER_ERROR",
@@ -90,13 +143,34 @@ export const AppErrorCodes = [
 // Creates a union export type
 export type AppErrorCode = typeof AppErrorCodes[number];
 
+export type ErrorDetails =
+  {
+    kind: "TESTCASE",
+    testcaseTournamentSubmissionId: number,
+    testcaseSubmissionId: number,
+    matchResolutionId: number | null,
+  } |
+  {
+    kind: "CODE",
+    line: number | null,
+    column: number | null,
+    message: string,
+  };
+
+export type ErrorResponse = {
+  code: AppErrorCode,
+  message: string,
+  retryable: boolean,
+  details: ErrorDetails | null,
+}
+
 async function fetchApiOrNetworkError<T>(url: string, props: object): Promise<Result<T, AppErrorCode>> {
   try {
     const [code, resp] = await fetchApi(url, props);
     if (code >= 200 && code < 300) {
       return { Ok: resp }
     } else {
-      return { Err: resp }
+      return { Err: (resp as ErrorResponse).code }
     }
   } catch (_) {
     return { Err: "NETWORK" };
@@ -115,6 +189,56 @@ export function submissionNew(props: SubmissionNewProps, server?: string): Promi
   return fetchApiOrNetworkError(undefToStr(server) + "pdarena/submission/new", props);
 }
 
+export const Strategies = [
+  "ALWAYS_COOPERATE",
+  "ALWAYS_DEFECT",
+  "TIT_FOR_TAT",
+  "GRIM_TRIGGER",
+  "RANDOM",
+  "TIT_FOR_TWO_TATS",
+  "SUSPICIOUS_TIT_FOR_TAT",
+] as const;
+
+export type Strategy = typeof Strategies[number];
+
+export const strategyNames: Record<Strategy, string> = {
+  ALWAYS_COOPERATE: "Always Cooperate",
+  ALWAYS_DEFECT: "Always Defect",
+  TIT_FOR_TAT: "Tit for Tat",
+  GRIM_TRIGGER: "Grim Trigger",
+  RANDOM: "Random",
+  TIT_FOR_TWO_TATS: "Tit for Two Tats",
+  SUSPICIOUS_TIT_FOR_TAT: "Suspicious Tit for Tat",
+};
+
+// exactly one of opponentSubmissionId and opponentStrategy must be given
+export type SubmissionTestRunProps = {
+  code: string,
+  opponentSubmissionId?: number,
+  opponentStrategy?: Strategy,
+  nRounds: number,
+  apiKey: string,
+}
+
+export type TestRunRound = {
+  round: number,
+  defected: boolean | null,
+  opponentDefected: boolean | null,
+  payoff: number | null,
+  opponentPayoff: number | null,
+  stdout: string,
+  stdoutTruncated: boolean,
+  stderr: string,
+  stderrTruncated: boolean,
+}
+
+export type SubmissionTestRun = {
+  rounds: TestRunRound[],
+}
+
+export function submissionTestRun(props: SubmissionTestRunProps, server?: string): Promise<Result<SubmissionTestRun, AppErrorCode>> {
+  return fetchApiOrNetworkError(undefToStr(server) + "pdarena/submission/test_run", props);
+}
 
 export type TournamentNewProps = {
   title: string,
@@ -122,6 +246,7 @@ export type TournamentNewProps = {
   apiKey: string,
   nRounds: number,
   nMatchups: number,
+  disallowedImports?: string[],
 }
 
 export function tournamentNew(props: TournamentNewProps, server?: string): Promise<Result<TournamentData, AppErrorCode>> {
@@ -135,6 +260,8 @@ export type TournamentDataNewProps = {
   nRounds: number,
   nMatchups: number,
   active: boolean,
+  // leave out to keep the current policy
+  disallowedImports?: string[],
   apiKey: string,
 }
 
@@ -148,6 +275,7 @@ export type TournamentSubmissionNewProps = {
   submissionId: number,
   kind: TournamentSubmissionKind,
   name: string,
+  autoCompete?: boolean,
   apiKey: string,
 }
 
@@ -155,35 +283,137 @@ export function tournamentSubmissionNew(props: TournamentSubmissionNewProps, ser
   return fetchApiOrNetworkError(undefToStr(server) + "pdarena/tournament_submission/new", props);
 }
 
+// only the tournament's creator can enter reference submissions
+// they can compete without validating first
+export type TournamentSubmissionNewReferenceProps = {
+  tournamentId: number,
+  strategy: Strategy,
+  // defaults to the strategy's name
+  name?: string,
+  kind: TournamentSubmissionKind,
+  apiKey: string,
+}
+
+export function tournamentSubmissionNewReference(props: TournamentSubmissionNewReferenceProps, server?: string): Promise<Result<TournamentSubmission, AppErrorCode>> {
+  return fetchApiOrNetworkError(undefToStr(server) + "pdarena/tournament_submission/new_reference", props);
+}
+
+export type TournamentSubmissionValidationReportProps = {
+  tournamentId: number,
+  submissionId: number,
+  apiKey: string,
+}
+
+export type ValidationFailure = {
+  matchResolutionId: number,
+  submissionId: number,
+  opponentSubmissionId: number,
+  matchup: number,
+  round: number,
+  stderrExcerpt: string,
+}
+
+export type TestcaseReport = {
+  testcase: TournamentSubmission,
+  nCompletedRounds: number,
+  nExpectedRounds: number,
+  failures: ValidationFailure[],
+}
+
+export type ValidationReport = {
+  tournamentId: number,
+  submissionId: number,
+  passed: boolean,
+  testcases: TestcaseReport[],
+}
+
+export function tournamentSubmissionValidationReport(props: TournamentSubmissionValidationReportProps, server?: string): Promise<Result<ValidationReport, AppErrorCode>> {
+  return fetchApiOrNetworkError(undefToStr(server) + "pdarena/tournament_submission/validation_report", props);
+}
+
 export type SubmissionViewProps = {
   submissionId?: number[],
   minCreationTime?: number,
   maxCreationTime?: number,
   creatorUserId?: number[],
+  limit?: number,
+  cursor?: string,
   apiKey: string,
 }
 
-export function submissionView(props: SubmissionViewProps, server?: string): Promise<Result<Submission[], AppErrorCode>> {
+export function submissionView(props: SubmissionViewProps, server?: string): Promise<Result<Page<Submission>, AppErrorCode>> {
   return fetchApiOrNetworkError(undefToStr(server) + "pdarena/submission/view", props);
 }
 
+export type ReferenceSubmission = {
+  referenceSubmissionId: number,
+  creationTime: number,
+  submission: Submission,
+  strategy: Strategy,
+  name: string,
+}
+
+export type ReferenceSubmissionViewProps = {
+  onlyRecent: boolean,
+}
+
+export function referenceSubmissionView(props: ReferenceSubmissionViewProps, server?: string): Promise<Result<ReferenceSubmission[], AppErrorCode>> {
+  return fetchApiOrNetworkError(undefToStr(server) + "pdarena/reference_submission/view", props);
+}
 
 export type TournamentDataViewProps = {
   tournamentDataId?: number[],
   minCreationTime?: number,
   maxCreationTime?: number,
+  minId?: number,
+  maxId?: number,
   creatorUserId?: number[],
   tournamentId?: number[],
   title?: string[],
   active?: boolean,
   onlyRecent: boolean,
+  limit?: number,
+  cursor?: string,
   apiKey: string,
 }
 
-export function tournamentDataView(props: TournamentDataViewProps, server?: string): Promise<Result<TournamentData[], AppErrorCode>> {
+export function tournamentDataView(props: TournamentDataViewProps, server?: string): Promise<Result<Page<TournamentData>, AppErrorCode>> {
   return fetchApiOrNetworkError(undefToStr(server) + "pdarena/tournament_data/view", props);
 }
 
+export type TournamentFinalization = {
+  tournamentFinalizationId: number,
+  creationTime: number,
+  creatorUserId: number,
+  tournament: Tournament,
+  nRounds: number,
+  nMatchups: number,
+}
+
+export type TournamentSummary = {
+  nCompete: number,
+  nValidate: number,
+  nTestcase: number,
+  nCancel: number,
+  finalized: boolean,
+}
+
+export type TournamentDetail = {
+  tournamentData: TournamentData,
+  tournamentSubmissions: TournamentSubmission[],
+  tournamentFinalization: TournamentFinalization | null,
+  summary: TournamentSummary,
+}
+
+export type TournamentDetailProps = {
+  tournamentId: number,
+  apiKey: string,
+}
+
+export function tournamentDetail(props: TournamentDetailProps, server?: string): Promise<Result<TournamentDetail, AppErrorCode>> {
+  return fetchApiOrNetworkError(undefToStr(server) + "pdarena/tournament/detail", props);
+}
+
 export type TournamentSubmissionViewProps = {
   tournamentSubmissionId?: number[],
   minCreationTime?: number,
@@ -193,10 +423,12 @@ export type TournamentSubmissionViewProps = {
   submissionId?: number[],
   kind?: TournamentSubmissionKind,
   onlyRecent: boolean,
+  limit?: number,
+  cursor?: string,
   apiKey: string,
 }
 
-export function tournamentSubmissionView(props: TournamentSubmissionViewProps, server?: string): Promise<Result<TournamentSubmission[], AppErrorCode>> {
+export function tournamentSubmissionView(props: TournamentSubmissionViewProps, server?: string): Promise<Result<Page<TournamentSubmission>, AppErrorCode>> {
   return fetchApiOrNetworkError(undefToStr(server) + "pdarena/tournament_submission/view", props);
 }
 
@@ -211,13 +443,123 @@ export type MatchResolutionViewProps = {
   round?: number[],
   matchup?: number[],
   onlyRecent: boolean,
+  limit?: number,
+  cursor?: string,
   apiKey: string,
 }
 
-export function matchResolutionView(props: MatchResolutionViewProps, server?: string): Promise<Result<MatchResolution[], AppErrorCode>> {
+export function matchResolutionView(props: MatchResolutionViewProps, server?: string): Promise<Result<Page<MatchResolution>, AppErrorCode>> {
   return fetchApiOrNetworkError(undefToStr(server) + "pdarena/match_resolution/view", props);
 }
 
+export type TournamentExportProps = {
+  tournamentId: number,
+  anonymize?: boolean,
+  apiKey: string,
+}
+
+// rows as stored, with the ids they had when exported
+export type TournamentArchive = {
+  version: number,
+  exportTime: number,
+  anonymized: boolean,
+  tournament: { tournamentId: number, creationTime: number, creatorUserId: number },
+  tournamentData: {
+    tournamentDataId: number,
+    creationTime: number,
+    creatorUserId: number,
+    tournamentId: number,
+    title: string,
+    description: string,
+    nRounds: number,
+    nMatchups: number,
+    active: boolean,
+    disallowedImports?: string[],
+  }[],
+  submissions: { submissionId: number, creationTime: number, creatorUserId: number, code: string }[],
+  tournamentSubmissions: {
+    tournamentSubmissionId: number,
+    creationTime: number,
+    creatorUserId: number,
+    tournamentId: number,
+    submissionId: number,
+    name: string,
+    kind: TournamentSubmissionKind,
+    autoCompete: boolean,
+  }[],
+  tournamentFinalization: {
+    tournamentFinalizationId: number,
+    creationTime: number,
+    creatorUserId: number,
+    tournamentId: number,
+    nRounds: number,
+    nMatchups: number,
+  } | null,
+  tournamentFinalizationEntries: {
+    tournamentFinalizationEntryId: number,
+    creationTime: number,
+    tournamentFinalizationId: number,
+    tournamentSubmissionId: number,
+    submissionId: number,
+    name: string,
+  }[],
+  matchResolutions: {
+    matchResolutionId: number,
+    submissionId: number,
+    opponentSubmissionId: number,
+    round: number,
+    matchup: number,
+    tournamentFinalizationId: number | null,
+    creationTime: number,
+    defected: boolean | null,
+    seed: number | null,
+  }[],
+}
+
+export function tournamentExport(props: TournamentExportProps, server?: string): Promise<Result<TournamentArchive, AppErrorCode>> {
+  return fetchApiOrNetworkError(undefToStr(server) + "pdarena/tournament/export", props);
+}
+
+export type MatchResolutionOutputProps = {
+  matchResolutionId: number,
+  apiKey: string,
+}
+
+export function matchResolutionOutput(props: MatchResolutionOutputProps, server?: string): Promise<Result<MatchResolutionOutput, AppErrorCode>> {
+  return fetchApiOrNetworkError(undefToStr(server) + "pdarena/match_resolution/output", props);
+}
+
+export type TournamentResultsProps = {
+  tournamentId: number,
+  format?: "csv" | "ndjson",
+  tournamentFinalizationId?: number,
+  apiKey: string,
+}
+
+// every recent round of the tournament, as the file the server streams back
+export async function tournamentResults(props: TournamentResultsProps, server?: string): Promise<Result<Blob, AppErrorCode>> {
+  const query = new URLSearchParams();
+  if (props.format !== undefined) {
+    query.set("format", props.format);
+  }
+  if (props.tournamentFinalizationId !== undefined) {
+    query.set("tournamentFinalizationId", props.tournamentFinalizationId.toString());
+  }
+  try {
+    const resp = await fetch(
+      undefToStr(server) + `pdarena/v1/tournaments/${props.tournamentId}/results?${query}`,
+      { headers: { "Authorization": `Bearer ${props.apiKey}` } }
+    );
+    if (resp.ok) {
+      return { Ok: await resp.blob() }
+    } else {
+      return { Err: ((await resp.json()) as ErrorResponse).code }
+    }
+  } catch (_) {
+    return { Err: "NETWORK" };
+  }
+}
+
 function wsRelativeUrl(relPath: string) {
   const protocol = window.location.protocol === 'https:' ? 'wss' : 'ws';
   return `${protocol}://${window.location.host}/api/${relPath}`
@@ -232,3 +574,18 @@ export function matchResolutionLiteStream(props: MatchResolutionViewProps, serve
   ws.addEventListener('open', () => ws.send(JSON.stringify(props)));
   return ws;
 }
+
+export type TournamentStandingsStreamProps = {
+  tournamentId: number,
+  tournamentFinalizationId?: number,
+}
+
+export function tournamentStandingsStream(props: TournamentStandingsStreamProps, server?: string): WebSocket {
+  const path = "pdarena/ws/tournament/standings/stream";
+  const url = server === undefined
+    ? wsRelativeUrl(path)
+    : server + path;
+  const ws = new WebSocket(url);
+  ws.addEventListener('open', () => ws.send(JSON.stringify(props)));
+  return ws;
+}